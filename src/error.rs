@@ -5,9 +5,20 @@
 
 // for documentation
 #[allow(unused_imports)]
-use crate::utils::level::{
-    BLACK_HOLE_FORMAT, BRAND_36_BITS, BURDENS_4_BITS, MAX_AUTHOR_LEN, MAX_DESCRIPTION_LEN,
-    MAX_NAME_LEN, VALID_MUSIC,
+use crate::utils::{
+    config::Quotas,
+    creator::{Creator, MAX_SECRET_LEN, MIN_SECRET_LEN},
+    duplicate::DuplicatePolicy,
+    filter::WordList,
+    level::{
+        BLACK_HOLE_FORMAT, BRAND_36_BITS, BURDENS_4_BITS, MAX_AUTHOR_LEN, MAX_DESCRIPTION_LEN,
+        MAX_NAME_LEN, VALID_MUSIC,
+    },
+    lock::DataLock,
+    ratelimit::RateLimiter,
+    replay::MAX_REPLAY_LEN,
+    report::MAX_COMMENT_LEN,
+    server::{MIN_PASSWORD_LEN, MIN_USERNAME_LEN},
 };
 
 /// The main error type, containing all possible fail-states of Voyager.
@@ -62,12 +73,30 @@ pub enum Error {
     /// characters were not in ([`BLACK_HOLE_FORMAT`]).
     #[error("invalid objects")]
     InvalidObjects,
+    /// Replay: The replay could not be verified to
+    /// reach the level's exit. See [`ReplayError`].
+    #[error("unverifiable replay: {0}")]
+    InvalidReplay(ReplayError),
+    /// Report: The reason was not one of `offensive`,
+    /// `spam`, `broken`, `stolen`, or `other`.
+    #[error("invalid report reason")]
+    InvalidReason,
+    /// Report: The comment was too long (>[`MAX_COMMENT_LEN`]).
+    #[error("invalid comment: {0}")]
+    InvalidComment(StringError),
     /// PUT and DELETE: The key was invalid. The key could not be
     /// parsed into a [ULID](https://github.com/ulid/spec) key.
     #[error("key error: {0}")]
     InvalidKey(#[from] ulid::DecodeError),
-    /// GET, POST, PUT, DELETE: The key was valid, but a matching
-    /// level was not found. For GET, this is the level check that
+    /// The public ID was invalid. It was not 16 hexadecimal digits.
+    #[error("invalid level id")]
+    InvalidId,
+    /// Import: The key hash was invalid. It was not
+    /// 64 hexadecimal digits.
+    #[error("invalid key hash")]
+    InvalidKeyHash,
+    /// GET, POST, PUT, DELETE: The key (or public ID) was valid,
+    /// but a matching level was not found. For GET, this is the level check that
     /// Endless Void does on startup (checking that all stored keys
     /// are in the Voyager database). For POST, this is the anti-
     /// orphan check that Endless Void does soon after sending a
@@ -76,10 +105,48 @@ pub enum Error {
     /// and DELETE, this is simply if the database has no matching level.
     #[error("level not found")]
     LevelNotFound,
+    /// Revisions: The level was found, but it has
+    /// no revision with the given number.
+    #[error("revision not found")]
+    RevisionNotFound,
     /// The user has been banned by use of the Web UI and is
     /// no longer allowed to upload, edit, or delete levels.
     #[error("you have been banned")]
     Banned,
+    /// POST and PUT: The name, description, or author (the
+    /// contained field) contains a word which is not allowed.
+    /// See [`WordList`].
+    #[error("the {0} contains a blocked word")]
+    BlockedWord(&'static str),
+    /// POST: The level's tiles and objects are identical to an
+    /// existing level's. See [`DuplicatePolicy`].
+    #[error("this level has already been uploaded")]
+    Duplicate,
+    /// POST: The user has uploaded too many levels. See [`QuotaError`].
+    #[error("quota exceeded: {0}")]
+    QuotaExceeded(QuotaError),
+    /// GET, POST, PUT, DELETE: The user has sent too many requests
+    /// of this kind recently. See [`RateLimiter`].
+    #[error("too many requests, retry after {retry_after} seconds")]
+    RateLimited {
+        /// How many seconds until the next request would be allowed.
+        retry_after: u64,
+    },
+    /// Creator: The secret was too short (<[`MIN_SECRET_LEN`])
+    /// or too long (>[`MAX_SECRET_LEN`]).
+    #[error("the secret must be between {MIN_SECRET_LEN} and {MAX_SECRET_LEN} bytes long")]
+    InvalidSecret,
+    /// Creator: The brand already has a [`Creator`] account.
+    #[error("this brand already has a creator account")]
+    CreatorExists,
+    /// POST and Creator: The creator credentials were malformed,
+    /// or the brand has no [`Creator`] account with that secret.
+    #[error("invalid creator credentials")]
+    Unauthorized,
+    /// POST and Creator: The level does not have the creator's
+    /// brand, or was not uploaded (or claimed) by the creator.
+    #[error("this level does not belong to the creator")]
+    NotOwner,
     /// The given IP adress to ban by use of the Web UI was invalid.
     #[error("invalid ip")]
     InvalidIp(#[from] std::net::AddrParseError),
@@ -91,6 +158,34 @@ pub enum Error {
     /// file (`./voyager.db`) containing the stored levels.
     #[error("bincode (de)serialization error: {0}")]
     Bincode(#[from] bincode::Error),
+    /// On startup, or when running a subcommand: Another Voyager
+    /// holds the lock on the `voyager` directory, see [`DataLock`].
+    #[error("another voyager is using the voyager directory, stop it first")]
+    Locked,
+    /// Web UI users: The username or password was too short,
+    /// see [`MIN_USERNAME_LEN`] and [`MIN_PASSWORD_LEN`].
+    #[error("usernames must be at least {MIN_USERNAME_LEN} and passwords at least {MIN_PASSWORD_LEN} characters long")]
+    InvalidUser,
+    /// Web UI users: A user with the username already exists.
+    #[error("a user with this username already exists")]
+    UserExists,
+    /// Web UI users: There is no user with the username.
+    #[error("user not found")]
+    UserNotFound,
+    /// Import: A level to import had the same public ID as an
+    /// existing level (or was an exact duplicate of one), and
+    /// conflicts abort the import.
+    #[error("conflicts with existing level {0}")]
+    ImportConflict(crate::prelude::PublicId),
+    /// Import: A line of an NDJSON export could not be deserialized.
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    /// Thumbnails: The thumbnail could not be encoded as a PNG image.
+    #[error("png error: {0}")]
+    Png(#[from] png::EncodingError),
+    /// On startup, if the metrics recorder could not be installed.
+    #[error("metrics error: {0}")]
+    Metrics(#[from] metrics_exporter_prometheus::BuildError),
     /// On startup, an error occured when asking for a username
     /// and password for the Web UI (probably a user interrupt).
     #[error("inquire error: {0}")]
@@ -119,6 +214,31 @@ pub enum NumberError {
     },
 }
 
+/// All quota-related Voyager errors. See [`Quotas`].
+#[derive(thiserror::Error, Debug)]
+// i don't want an enum with the name "Quota" lol
+#[allow(clippy::module_name_repetitions)]
+pub enum QuotaError {
+    /// POST: The IP adress has uploaded too many levels today.
+    #[error("you may only upload {max} levels per day")]
+    DailyUploads {
+        /// See [`Quotas::daily_per_ip`].
+        max: u32,
+    },
+    /// POST: The author brand has uploaded too many levels today.
+    #[error("this brand may only upload {max} levels per day")]
+    DailyBrandUploads {
+        /// See [`Quotas::daily_per_brand`].
+        max: u32,
+    },
+    /// POST: The IP adress has too many levels uploaded.
+    #[error("you may only have {max} levels uploaded at once")]
+    LiveLevels {
+        /// See [`Quotas::max_live_per_ip`].
+        max: usize,
+    },
+}
+
 /// All string-related Voyager errors.
 #[derive(thiserror::Error, Debug)]
 // i don't want an enum with the name "String" lol
@@ -140,6 +260,8 @@ pub enum StringError {
         /// Author may at most be [`MAX_AUTHOR_LEN`] long.
         ///
         /// Description may at most be [`MAX_DESCRIPTION_LEN`] long.
+        ///
+        /// Report comments may at most be [`MAX_COMMENT_LEN`] long.
         max: u64,
         /// What the user's input was.
         found: u64,
@@ -150,7 +272,343 @@ pub enum StringError {
     TooShort,
 }
 
-use axum::http::StatusCode;
+/// All replay-related Voyager errors.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+// i don't want an enum with the name "Replay" lol
+#[allow(clippy::module_name_repetitions)]
+pub enum ReplayError {
+    /// A move was not one of `U`, `D`, `L`, `R`, or `A`.
+    #[error("invalid move: {0}")]
+    InvalidMove(char),
+    /// The replay had too many moves.
+    #[error("too many moves: {found} > {max}")]
+    TooLong {
+        /// Replays may at most be [`MAX_REPLAY_LEN`] moves long.
+        max: u64,
+        /// How many moves the replay had.
+        found: u64,
+    },
+    /// The level's tiles or objects could
+    /// not be decoded, so it cannot be replayed.
+    #[error("level could not be decoded")]
+    UndecodableLevel,
+    /// The level does not have exactly one player.
+    #[error("level does not have exactly one player")]
+    NoPlayer,
+    /// The player fell into a pit.
+    #[error("fell into a pit after {step} steps")]
+    Fell {
+        /// How many steps were taken before falling.
+        step: u32,
+    },
+    /// The replay kept going after reaching the exit.
+    #[error("moves left after reaching the exit after {step} steps")]
+    MovesAfterExit {
+        /// How many steps were taken before reaching the exit.
+        step: u32,
+    },
+    /// The replay ended without reaching the exit.
+    #[error("exit not reached")]
+    ExitNotReached,
+}
+
+impl Error {
+    /// The error's kind, in `snake_case`, e.g. `invalid_name`. Used
+    /// as the error code in JSON responses (see [`Details`]) and to
+    /// label the `voyager_errors_total` metric, so it must not change.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::InvalidStructure => "invalid_structure",
+            Self::InvalidVersion(_) => "invalid_version",
+            Self::InvalidName(_) => "invalid_name",
+            Self::InvalidDescription(_) => "invalid_description",
+            Self::InvalidMusic(_) => "invalid_music",
+            Self::NotASong => "not_a_song",
+            Self::InvalidAuthor(_) => "invalid_author",
+            Self::InvalidBrand(_) => "invalid_brand",
+            Self::InvalidBurdens(_) => "invalid_burdens",
+            Self::InvalidTiles => "invalid_tiles",
+            Self::InvalidObjects => "invalid_objects",
+            Self::InvalidReplay(_) => "invalid_replay",
+            Self::InvalidReason => "invalid_reason",
+            Self::InvalidComment(_) => "invalid_comment",
+            Self::InvalidKey(_) => "invalid_key",
+            Self::InvalidId => "invalid_id",
+            Self::InvalidKeyHash => "invalid_key_hash",
+            Self::LevelNotFound => "level_not_found",
+            Self::RevisionNotFound => "revision_not_found",
+            Self::Banned => "banned",
+            Self::BlockedWord(_) => "blocked_word",
+            Self::Duplicate => "duplicate",
+            Self::QuotaExceeded(_) => "quota_exceeded",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::InvalidSecret => "invalid_secret",
+            Self::CreatorExists => "creator_exists",
+            Self::Unauthorized => "unauthorized",
+            Self::NotOwner => "not_owner",
+            Self::InvalidIp(_) => "invalid_ip",
+            Self::Io(_) => "io",
+            Self::Bincode(_) => "bincode",
+            Self::Locked => "locked",
+            Self::InvalidUser => "invalid_user",
+            Self::UserExists => "user_exists",
+            Self::UserNotFound => "user_not_found",
+            Self::ImportConflict(_) => "import_conflict",
+            Self::Json(_) => "json",
+            Self::Png(_) => "png",
+            Self::Metrics(_) => "metrics",
+            Self::Inquire(_) => "inquire",
+        }
+    }
+
+    /// The input field the error is about, e.g. `name`, if any.
+    #[must_use]
+    pub const fn field(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidVersion(_) => Some("version"),
+            Self::InvalidName(_) => Some("name"),
+            Self::InvalidDescription(_) => Some("description"),
+            Self::InvalidMusic(_) | Self::NotASong => Some("music"),
+            Self::InvalidAuthor(_) => Some("author"),
+            Self::InvalidBrand(_) => Some("brand"),
+            Self::InvalidBurdens(_) => Some("burdens"),
+            Self::InvalidTiles => Some("tiles"),
+            Self::InvalidObjects => Some("objects"),
+            Self::InvalidReplay(_) => Some("replay"),
+            Self::InvalidReason => Some("reason"),
+            Self::InvalidComment(_) => Some("comment"),
+            Self::InvalidKey(_) => Some("key"),
+            Self::InvalidId => Some("id"),
+            Self::InvalidKeyHash => Some("key_hash"),
+            Self::InvalidIp(_) => Some("ip"),
+            Self::BlockedWord(field) => Some(field),
+            _ => None,
+        }
+    }
+
+    /// The error's machine-readable details.
+    #[must_use]
+    pub fn details(&self) -> Details {
+        let (reason, max, found) = match self {
+            Self::InvalidVersion(why) | Self::InvalidBrand(why) | Self::InvalidBurdens(why) => {
+                (Some(why.kind()), why.max(), why.found())
+            }
+            Self::InvalidName(why)
+            | Self::InvalidDescription(why)
+            | Self::InvalidMusic(why)
+            | Self::InvalidAuthor(why)
+            | Self::InvalidComment(why) => (Some(why.kind()), why.max(), why.found()),
+            Self::InvalidReplay(why) => (Some(why.kind()), why.max(), why.found()),
+            Self::QuotaExceeded(why) => (Some(why.kind()), Some(why.max()), None),
+            _ => (None, None, None),
+        };
+        Details {
+            code: self.kind(),
+            reason,
+            field: self.field(),
+            max,
+            found,
+            retry_after: match self {
+                Self::RateLimited { retry_after } => Some(*retry_after),
+                _ => None,
+            },
+            message: self.to_string(),
+        }
+    }
+}
+
+impl NumberError {
+    /// The error's kind, in `snake_case`, e.g. `too_big`.
+    /// Used as the reason in JSON responses, see [`Details`].
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::NotANumber(_) => "not_a_number",
+            Self::TooBig { .. } => "too_big",
+        }
+    }
+
+    /// The biggest allowed number, if it was too big.
+    #[must_use]
+    pub const fn max(&self) -> Option<u64> {
+        match self {
+            Self::TooBig { max, .. } => Some(*max),
+            Self::NotANumber(_) => None,
+        }
+    }
+
+    /// The number, if it was too big.
+    #[must_use]
+    pub const fn found(&self) -> Option<u64> {
+        match self {
+            Self::TooBig { found, .. } => Some(*found),
+            Self::NotANumber(_) => None,
+        }
+    }
+}
+
+impl StringError {
+    /// The error's kind, in `snake_case`, e.g. `too_long`.
+    /// Used as the reason in JSON responses, see [`Details`].
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Base64(_) => "base64",
+            Self::FromUtf8(_) => "utf8",
+            Self::TooLong { .. } => "too_long",
+            Self::TooShort => "too_short",
+        }
+    }
+
+    /// The longest allowed length, if the input was too long.
+    #[must_use]
+    pub const fn max(&self) -> Option<u64> {
+        match self {
+            Self::TooLong { max, .. } => Some(*max),
+            _ => None,
+        }
+    }
+
+    /// The input's length, if it was too long or too short.
+    #[must_use]
+    pub const fn found(&self) -> Option<u64> {
+        match self {
+            Self::TooLong { found, .. } => Some(*found),
+            Self::TooShort => Some(0),
+            _ => None,
+        }
+    }
+}
+
+impl QuotaError {
+    /// The error's kind, in `snake_case`, e.g. `daily_uploads`.
+    /// Used as the reason in JSON responses, see [`Details`].
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::DailyUploads { .. } => "daily_uploads",
+            Self::DailyBrandUploads { .. } => "daily_brand_uploads",
+            Self::LiveLevels { .. } => "live_levels",
+        }
+    }
+
+    /// How many levels are allowed.
+    #[must_use]
+    pub const fn max(&self) -> u64 {
+        match self {
+            Self::DailyUploads { max } | Self::DailyBrandUploads { max } => *max as u64,
+            Self::LiveLevels { max } => *max as u64,
+        }
+    }
+}
+
+impl ReplayError {
+    /// The error's kind, in `snake_case`, e.g. `exit_not_reached`.
+    /// Used as the reason in JSON responses, see [`Details`].
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::InvalidMove(_) => "invalid_move",
+            Self::TooLong { .. } => "too_long",
+            Self::UndecodableLevel => "undecodable_level",
+            Self::NoPlayer => "no_player",
+            Self::Fell { .. } => "fell",
+            Self::MovesAfterExit { .. } => "moves_after_exit",
+            Self::ExitNotReached => "exit_not_reached",
+        }
+    }
+
+    /// The most allowed moves, if the replay had too many.
+    #[must_use]
+    pub const fn max(&self) -> Option<u64> {
+        match self {
+            Self::TooLong { max, .. } => Some(*max),
+            _ => None,
+        }
+    }
+
+    /// How many moves the replay had, if it had too many.
+    #[must_use]
+    pub const fn found(&self) -> Option<u64> {
+        match self {
+            Self::TooLong { found, .. } => Some(*found),
+            _ => None,
+        }
+    }
+}
+
+/// An [`Error`]'s machine-readable details, sent instead of the
+/// plain-text message to clients asking for JSON, see [`negotiate`].
+///
+/// For example, a too long name is sent as
+/// `{"code": "invalid_name", "reason": "too_long", "field": "name",
+/// "max": 30, "found": 40, "message": "invalid name: input was too long: 40 > 30"}`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Details {
+    /// See [`Error::kind`].
+    pub code: &'static str,
+    /// What exactly was wrong, e.g. `too_long` or `base64`, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<&'static str>,
+    /// See [`Error::field`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<&'static str>,
+    /// The biggest allowed value (or length), if it was exceeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<u64>,
+    /// The value (or length) that was sent, if it was not allowed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub found: Option<u64>,
+    /// How many seconds until the next request would be allowed, if rate limited.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+    /// The plain-text message, as sent to other clients.
+    pub message: String,
+}
+
+/// Whether the client asked for JSON, i.e. whether
+/// its `Accept` header contains `application/json`.
+#[must_use]
+pub fn wants_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json"))
+}
+
+/// Sends errors as JSON (see [`Details`]) to clients whose `Accept`
+/// header contains `application/json`. Other clients still get
+/// the plain-text message.
+pub async fn negotiate(request: Request, next: Next) -> Response {
+    let json = wants_json(request.headers());
+    let mut response = next.run(request).await;
+    if !json {
+        return response;
+    }
+    let Some(details) = response.extensions_mut().remove::<Details>() else {
+        return response;
+    };
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    let body = serde_json::to_vec(&details).unwrap_or_default();
+    Response::from_parts(parts, Body::from(body))
+}
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{
+        header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE, RETRY_AFTER},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    middleware::Next,
+    response::Response,
+    Extension,
+};
 use tracing::{info, warn};
 
 impl axum::response::IntoResponse for Error {
@@ -158,8 +616,20 @@ impl axum::response::IntoResponse for Error {
     #[allow(clippy::cognitive_complexity)]
     fn into_response(self) -> axum::response::Response {
         let message = self.to_string();
+        let details = self.details();
+        metrics::counter!("voyager_errors_total", "kind" => self.kind()).increment(1);
+        if let Self::RateLimited { retry_after } = self {
+            info!("{message}");
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(RETRY_AFTER, retry_after.to_string())],
+                Extension(details),
+                message,
+            )
+                .into_response();
+        }
         let status = match self {
-            Self::LevelNotFound => {
+            Self::LevelNotFound | Self::RevisionNotFound => {
                 info!("{self}");
                 StatusCode::NOT_FOUND
             }
@@ -171,12 +641,32 @@ impl axum::response::IntoResponse for Error {
                 warn!("{why}");
                 StatusCode::INTERNAL_SERVER_ERROR
             }
+            Self::Png(why) => {
+                warn!("{why}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
             Self::Banned => StatusCode::FORBIDDEN,
+            Self::Unauthorized => {
+                info!("{self}");
+                StatusCode::UNAUTHORIZED
+            }
+            Self::NotOwner => {
+                info!("{self}");
+                StatusCode::FORBIDDEN
+            }
+            Self::Duplicate | Self::CreatorExists => {
+                info!("{self}");
+                StatusCode::CONFLICT
+            }
+            Self::QuotaExceeded(why) => {
+                info!("{why}");
+                StatusCode::TOO_MANY_REQUESTS
+            }
             other => {
                 info!("{other}");
                 StatusCode::BAD_REQUEST
             }
         };
-        (status, message).into_response()
+        (status, Extension(details), message).into_response()
     }
 }
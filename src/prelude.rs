@@ -1,10 +1,13 @@
 //! Common items used throughout Voyager.
 pub use crate::error::Error;
 pub use crate::error::NumberError;
+pub use crate::error::QuotaError;
+pub use crate::error::ReplayError;
 pub use crate::error::StringError;
 pub use crate::utils::level::Key;
 pub use crate::utils::level::Level;
 pub use crate::utils::level::Parsed;
+pub use crate::utils::level::PublicId;
 pub use crate::utils::server::Backend;
 pub use crate::utils::server::Credentials;
 pub use crate::utils::server::SharedAppState;
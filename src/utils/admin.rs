@@ -0,0 +1,248 @@
+//! Offline administration, for when the server is stopped: listing,
+//! searching, showing, and deleting levels, banning and unbanning IP
+//! adresses, purging orphans, and managing Web UI users and creator
+//! accounts.
+//!
+//! Everything here goes through the same [`AppState`] and [`Backend`]
+//! methods as the server and the Web UI, see [`Command`](crate::utils::cli::Command).
+
+use crate::prelude::*;
+use crate::utils::{
+    grid::{Grid, GRID_WIDTH},
+    index::Filter,
+    level::Brand,
+    server::{AppState, MIN_PASSWORD_LEN},
+    transfer::{Record, Status},
+};
+use inquire::{min_length, Password};
+use itertools::Itertools;
+use std::io::IsTerminal;
+
+/// Prints every level matching the filter (and whose name contains
+/// `name`, ignoring case), oldest first, as a table or as JSON
+/// (see [`Record`]).
+///
+/// # Errors
+/// Returns an error if the filter is invalid.
+pub fn list(filter: &Filter, name: Option<&str>, json: bool) -> Result<()> {
+    let db = AppState::load();
+    let name = name.map(str::to_lowercase);
+    let levels = db
+        .parsed_levels_matching(filter)?
+        .into_iter()
+        .filter(|level| {
+            name.as_ref()
+                .is_none_or(|name| level.name.to_string().to_lowercase().contains(name))
+        })
+        .sorted_by_key(|level| (level.uploaded.to_string(), level.id))
+        .collect::<Vec<Parsed>>();
+    if json {
+        let records = levels
+            .into_iter()
+            .map(|level| {
+                let status = if db.is_hidden(level.id) {
+                    Status::Hidden
+                } else {
+                    Status::Public
+                };
+                Record::new(level, status, None, &db)
+            })
+            .collect::<Vec<Record>>();
+        println!("{}", serde_json::to_string_pretty(&records)?);
+        return Ok(());
+    }
+    println!(
+        "{:<16}  {:<8}  {:<11}  {:<15}  {:<20}  NAME",
+        "ID", "UPLOADED", "BRAND", "UPLOADER", "AUTHOR"
+    );
+    for level in &levels {
+        println!(
+            "{:<16}  {:<8}  {:<11}  {:<15}  {:<20}  {}",
+            level.id.to_string(),
+            level.uploaded.to_string(),
+            level.brand.to_string(),
+            level.uploader.to_string(),
+            level.author.to_string(),
+            level.name
+        );
+    }
+    println!("{} levels", levels.len());
+    Ok(())
+}
+
+/// Prints a level (public or held for review), decoded,
+/// along with its tiles and objects laid out as a grid.
+///
+/// # Errors
+/// Returns an error if the public ID is invalid, or
+/// [`Error::LevelNotFound`] if there is no such level.
+pub fn show(id: &str) -> Result<()> {
+    let db = AppState::load();
+    let id = id.parse()?;
+    let level = db.get(id).or_else(|_| db.get_pending(id))?.into_parsed()?;
+    let stats = db.stats(id).unwrap_or_default();
+    println!("ID: {id}\nUploader: {}\n{level}", level.uploader);
+    println!(
+        "Hidden: {}\nPending: {}\nOwner: {}",
+        db.is_hidden(id),
+        db.is_pending(id),
+        db.owner(id)
+            .map_or_else(|| "none".to_string(), |brand| brand.to_string()),
+    );
+    println!("Plays: {}\nClears: {}", stats.plays, stats.clears);
+    println!("\nTiles:\n{}", render(level.tiles.decode()));
+    println!("Objects:\n{}", render(level.objects.decode()));
+    Ok(())
+}
+
+/// Lays out a grid's cells row by row, by their two-letter ids.
+fn render(grid: Option<Grid>) -> String {
+    let Some(grid) = grid else {
+        return "(could not be decoded)\n".to_string();
+    };
+    grid.cells().fold(String::new(), |mut rows, (x, _, cell)| {
+        rows.push_str(cell.id());
+        rows.push(if x + 1 == GRID_WIDTH { '\n' } else { ' ' });
+        rows
+    })
+}
+
+/// Deletes a level (public or held for review) by its key.
+///
+/// # Errors
+/// Returns an error if the key is invalid, or
+/// [`Error::LevelNotFound`] if there is no such level.
+pub fn delete(key: &str) -> Result<()> {
+    let db = AppState::load();
+    let id = db.find_key(&key.parse()?)?;
+    db.delete(id)?;
+    println!("Deleted {id}.");
+    Ok(())
+}
+
+/// Bans an IP adress, deleting every level uploaded by it.
+///
+/// # Errors
+/// Returns an error if the IP adress is invalid.
+pub fn ban(ip: &str) -> Result<()> {
+    AppState::load().ban(ip)?;
+    println!("Banned {ip}.");
+    Ok(())
+}
+
+/// Unbans an IP adress.
+///
+/// # Errors
+/// Returns an error if the IP adress is invalid.
+pub fn unban(ip: &str) -> Result<()> {
+    if AppState::load().unban(ip)? {
+        println!("Unbanned {ip}.");
+    } else {
+        println!("{ip} was not banned.");
+    }
+    Ok(())
+}
+
+/// Deletes every orphan, or only those staged more than
+/// [`Config::orphan_ttl`](crate::utils::config::Config::orphan_ttl)
+/// seconds ago.
+pub fn purge_orphans(expired: bool) {
+    let db = AppState::load();
+    let count = if expired {
+        db.purge_expired_orphans()
+    } else {
+        db.purge_orphans()
+    };
+    println!("Purged {count} orphans.");
+}
+
+/// Prints every Web UI user's username.
+///
+/// # Errors
+/// Returns an error if the Web UI users could not be loaded.
+pub fn list_users() -> Result<()> {
+    for username in Backend::open()?.usernames() {
+        println!("{username}");
+    }
+    Ok(())
+}
+
+/// Adds a Web UI user, asking for their password.
+///
+/// # Errors
+/// Returns an error if the user could not be added.
+pub fn add_user(username: &str) -> Result<()> {
+    let password = ask_password()?;
+    Backend::open()?.add_user(username, &password)?;
+    println!("Added {username}.");
+    Ok(())
+}
+
+/// Removes a Web UI user.
+///
+/// # Errors
+/// Returns an error if the user could not be removed.
+pub fn remove_user(username: &str) -> Result<()> {
+    Backend::open()?.remove_user(username)?;
+    println!("Removed {username}.");
+    Ok(())
+}
+
+/// Changes a Web UI user's password, asking for the new one.
+///
+/// # Errors
+/// Returns an error if the password could not be changed.
+pub fn set_password(username: &str) -> Result<()> {
+    let password = ask_password()?;
+    Backend::open()?.set_password(username, &password)?;
+    println!("Changed {username}'s password.");
+    Ok(())
+}
+
+/// Prints every creator account, oldest first.
+pub fn list_creators() {
+    let db = AppState::load();
+    println!(
+        "{:<11}  {:<10}  {:<6}  AUTHOR",
+        "BRAND", "REGISTERED", "LEVELS"
+    );
+    let creators = db.creators();
+    for creator in &creators {
+        println!(
+            "{:<11}  {:<10}  {:<6}  {}",
+            creator.brand.to_string(),
+            creator.registered,
+            db.owned_by(creator.brand).len(),
+            creator.author
+        );
+    }
+    println!("{} creators", creators.len());
+}
+
+/// Deletes a creator account, see [`AppState::revoke`].
+///
+/// # Errors
+/// Returns an error if the brand is invalid.
+pub fn revoke_creator(brand: &str) -> Result<()> {
+    let brand = Brand::try_from(brand)?;
+    if AppState::load().revoke(brand) {
+        println!("Revoked {brand}'s creator account.");
+    } else {
+        println!("{brand} has no creator account.");
+    }
+    Ok(())
+}
+
+/// Asks for a password, or reads it from the first
+/// line of stdin if it is not a terminal.
+fn ask_password() -> Result<String> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Ok(Password::new("password:")
+            .with_validator(min_length!(MIN_PASSWORD_LEN))
+            .prompt()?);
+    }
+    let mut password = String::new();
+    stdin.read_line(&mut password)?;
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
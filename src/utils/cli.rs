@@ -0,0 +1,247 @@
+//! Contains [`Cli`], Voyager's command-line arguments.
+//!
+//! Without a subcommand, Voyager starts the server,
+//! same as `voyager serve`. Every other subcommand works
+//! on the `voyager` directory directly, and refuses to run
+//! while the server is running (and vice versa), see [`DataLock`].
+
+use crate::prelude::*;
+use crate::utils::{admin, index::Filter, lock::DataLock, server::start_voyager, transfer};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+/// The server back-end for Endless Void.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// What to do. Defaults to starting the server.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Voyager's subcommands.
+#[derive(Debug, Default, Subcommand)]
+pub enum Command {
+    /// Starts the server.
+    #[default]
+    Serve,
+    /// Exports every level (including levels
+    /// held for review) from `voyager/levels.db`.
+    ///
+    /// Orphans, creator accounts, revisions, and leaderboards
+    /// are not exported, and are lost when importing the levels
+    /// into a new database.
+    Export {
+        /// What to export the levels as.
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+        /// The file (for NDJSON) or directory (for .vsl) to export to.
+        output: PathBuf,
+    },
+    /// Imports levels into `voyager/levels.db`, validating
+    /// each one. The server should not be running.
+    Import {
+        /// What the levels to import are.
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+        /// What to do with levels that already exist.
+        #[arg(long, value_enum, default_value_t)]
+        on_conflict: Conflict,
+        /// Only print what would be imported, without
+        /// changing the database.
+        #[arg(long)]
+        dry_run: bool,
+        /// The file (for NDJSON) or directory (for .vsl) to import from.
+        input: PathBuf,
+    },
+    /// Lists, shows, and deletes levels.
+    Levels {
+        #[command(subcommand)]
+        command: LevelCommand,
+    },
+    /// Bans an IP adress, deleting every level uploaded by it.
+    Ban {
+        /// The IP adress to ban.
+        ip: String,
+    },
+    /// Unbans an IP adress.
+    Unban {
+        /// The IP adress to unban.
+        ip: String,
+    },
+    /// Deletes every orphan (a level whose key was never confirmed).
+    PurgeOrphans {
+        /// Only delete orphans older than the orphan time-to-live setting.
+        #[arg(long)]
+        expired: bool,
+    },
+    /// Manages Web UI users.
+    Users {
+        #[command(subcommand)]
+        command: UserCommand,
+    },
+    /// Lists and revokes creator accounts.
+    Creators {
+        #[command(subcommand)]
+        command: CreatorCommand,
+    },
+}
+
+/// Subcommands for levels.
+#[derive(Debug, Subcommand)]
+pub enum LevelCommand {
+    /// Lists every public level matching the search, oldest first.
+    List {
+        #[command(flatten)]
+        search: Search,
+        /// Prints the levels as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Shows a level, decoded.
+    Show {
+        /// The level's public ID.
+        id: String,
+    },
+    /// Deletes a level.
+    Delete {
+        /// The level's key.
+        key: String,
+    },
+}
+
+/// What levels to list. Every given option must match.
+#[derive(Debug, Args)]
+pub struct Search {
+    /// The uploader's IP adress.
+    #[arg(long)]
+    uploader: Option<String>,
+    /// The author brand.
+    #[arg(long)]
+    brand: Option<String>,
+    /// The author name, ignoring case and extra whitespace.
+    #[arg(long)]
+    author: Option<String>,
+    /// The upload date, in yyyymmdd format.
+    #[arg(long)]
+    uploaded: Option<String>,
+    /// Text the level's name contains, ignoring case.
+    #[arg(long)]
+    name: Option<String>,
+}
+
+/// Subcommands for Web UI users.
+#[derive(Debug, Subcommand)]
+pub enum UserCommand {
+    /// Lists every user.
+    List,
+    /// Adds a user, asking for their password.
+    Add {
+        /// The new user's username.
+        username: String,
+    },
+    /// Removes a user.
+    Remove {
+        /// The user's username.
+        username: String,
+    },
+    /// Changes a user's password, asking for the new one.
+    Password {
+        /// The user's username.
+        username: String,
+    },
+}
+
+/// Subcommands for creator accounts.
+#[derive(Debug, Subcommand)]
+pub enum CreatorCommand {
+    /// Lists every creator account.
+    List,
+    /// Deletes a creator account. Its levels are kept,
+    /// but no longer belong to anyone.
+    Revoke {
+        /// The creator's author brand.
+        brand: String,
+    },
+}
+
+/// How levels are exported and imported, see [`transfer`] for details.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Newline-delimited JSON, one level per line.
+    #[default]
+    Ndjson,
+    /// A directory of .vsl files, each holding one level's data.
+    Vsl,
+}
+
+/// What to do when an imported level already exists, that is,
+/// it has the same public ID as an existing level or, if it has
+/// no public ID, it is an exact duplicate of one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Conflict {
+    /// Keeps the existing level.
+    #[default]
+    Skip,
+    /// Edits the existing level, keeping its public
+    /// ID, key, and upload date.
+    Replace,
+    /// Imports nothing at all.
+    Abort,
+}
+
+impl Cli {
+    /// Whether the subcommand starts the server.
+    #[must_use]
+    pub const fn serves(&self) -> bool {
+        matches!(self.command, None | Some(Command::Serve))
+    }
+
+    /// Runs the subcommand.
+    pub async fn run(self) -> Result<()> {
+        // held until the server stops, or the subcommand is done
+        let _lock = DataLock::acquire()?;
+        match self.command.unwrap_or_default() {
+            Command::Serve => start_voyager().await,
+            Command::Export { format, output } => transfer::export(format, &output),
+            Command::Import {
+                format,
+                on_conflict,
+                dry_run,
+                input,
+            } => transfer::import(format, &input, on_conflict, dry_run),
+            Command::Levels { command } => match command {
+                LevelCommand::List { search, json } => {
+                    let filter = Filter {
+                        uploader: search.uploader,
+                        brand: search.brand,
+                        author: search.author,
+                        uploaded: search.uploaded,
+                    };
+                    admin::list(&filter, search.name.as_deref(), json)
+                }
+                LevelCommand::Show { id } => admin::show(&id),
+                LevelCommand::Delete { key } => admin::delete(&key),
+            },
+            Command::Ban { ip } => admin::ban(&ip),
+            Command::Unban { ip } => admin::unban(&ip),
+            Command::PurgeOrphans { expired } => {
+                admin::purge_orphans(expired);
+                Ok(())
+            }
+            Command::Users { command } => match command {
+                UserCommand::List => admin::list_users(),
+                UserCommand::Add { username } => admin::add_user(&username),
+                UserCommand::Remove { username } => admin::remove_user(&username),
+                UserCommand::Password { username } => admin::set_password(&username),
+            },
+            Command::Creators { command } => match command {
+                CreatorCommand::List => {
+                    admin::list_creators();
+                    Ok(())
+                }
+                CreatorCommand::Revoke { brand } => admin::revoke_creator(&brand),
+            },
+        }
+    }
+}
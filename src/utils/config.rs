@@ -0,0 +1,277 @@
+//! Contains [`Config`], Voyager's settings.
+//!
+//! Settings are read from `voyager/config.toml` on startup. Every
+//! setting is optional, and missing settings use their default value.
+//! For example:
+//!
+//! ```toml
+//! max_revisions = 10
+//! orphan_ttl = 86400
+//! duplicates = "reject"
+//! report_threshold = 5
+//!
+//! [quotas]
+//! daily_per_ip = 10
+//!
+//! [rate_limits]
+//! post = { burst = 5, per_minute = 10 }
+//! get = { burst = 30, per_minute = 60 }
+//!
+//! [metrics]
+//! listen = "127.0.0.1:9100"
+//!
+//! [logging]
+//! format = "json"
+//! stdout = "info,voyager::utils::webui=debug"
+//! access_log = "common"
+//! retention_days = 14
+//! ```
+
+use crate::utils::duplicate::DuplicatePolicy;
+use serde::Deserialize;
+use std::{fs::read_to_string, net::SocketAddr, num::NonZeroU32};
+use tracing::info;
+
+/// Voyager's settings. See the module documentation for details.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// How many previous revisions of a level are kept when it is
+    /// edited. The oldest revisions are forgotten first.
+    pub max_revisions: usize,
+    /// How many seconds an orphan (see
+    /// [`orphanage`](crate::utils::routers::post::orphanage))
+    /// is kept before it is purged.
+    pub orphan_ttl: u64,
+    /// How many seconds a deleted level is remembered as deleted (see
+    /// [`changes`](crate::utils::routers::get::changes)). Clients that
+    /// last synced longer ago than this must sync every level again.
+    pub tombstone_ttl: u64,
+    /// How many requests each IP adress may send, see [`RateLimits`].
+    pub rate_limits: RateLimits,
+    /// How many levels each IP adress and brand may upload, see [`Quotas`].
+    pub quotas: Quotas,
+    /// What to do with uploaded (or edited) levels that are exact duplicates
+    /// of existing ones, see [`DuplicatePolicy`].
+    pub duplicates: DuplicatePolicy,
+    /// How similar (between 0 and 1) two levels must be to be listed
+    /// as possible duplicates in the Web UI, see
+    /// [`Content::similarity`](crate::utils::duplicate::Content::similarity).
+    pub near_duplicate_similarity: f64,
+    /// How many players must report a level for it to be hidden from
+    /// the level list until a moderator looks at it, or 0 to never
+    /// hide reported levels.
+    pub report_threshold: usize,
+    /// How many seconds the server keeps serving requests after it is
+    /// told to shut down, while [`readyz`](crate::utils::routers::health::readyz)
+    /// already reports it as not ready, so that load balancers stop
+    /// sending it requests first.
+    pub shutdown_delay: u64,
+    /// Where and to whom the Prometheus metrics are served, see [`Metrics`].
+    pub metrics: Metrics,
+    /// How Voyager logs, see [`Logging`].
+    pub logging: Logging,
+}
+
+/// How Voyager logs, to stdout (stderr for subcommands other
+/// than `serve`) and to daily files in `voyager/logs`.
+/// See [`logging`](crate::utils::logging).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Logging {
+    /// The format of the logs on stdout.
+    pub format: LogFormat,
+    /// The format of the log files.
+    pub file_format: LogFormat,
+    /// Which levels to log to stdout, per module, e.g.
+    /// `info,voyager::utils::webui=debug`. See
+    /// [`Targets`](tracing_subscriber::filter::Targets) for the syntax.
+    pub stdout: String,
+    /// Which levels to log to the log files, per module, like [`Self::stdout`].
+    pub file: String,
+    /// How every request is logged once answered, see [`AccessLog`].
+    pub access_log: AccessLog,
+    /// How many days of log files to keep (one file per day, the oldest
+    /// deleted on startup and at midnight), or 0 to keep every log file.
+    pub retention_days: usize,
+}
+
+/// The format of logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Multi-line, human-readable logs.
+    Pretty,
+    /// Single-line, human-readable logs.
+    Full,
+    /// Shorter single-line, human-readable logs.
+    Compact,
+    /// One JSON object per line, including the request's span.
+    Json,
+}
+
+/// How every request is logged once answered, along with its request ID.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLog {
+    /// With the method, route, status, latency, and client IP
+    /// adress as separate fields, for [`LogFormat::Json`].
+    #[default]
+    Json,
+    /// In Common Log Format, with the latency appended.
+    Common,
+    /// Not at all.
+    Off,
+}
+
+impl Default for Logging {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Pretty,
+            file_format: LogFormat::Full,
+            stdout: "info".to_string(),
+            file: "debug".to_string(),
+            access_log: AccessLog::default(),
+            retention_days: 0,
+        }
+    }
+}
+
+/// Where and to whom the Prometheus metrics (see
+/// [`metrics`](crate::utils::metrics)) are served.
+///
+/// If neither is set, the metrics are not served at all.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Metrics {
+    /// A separate adress to serve `/voyager/metrics` on, instead
+    /// of port 3000, e.g. `127.0.0.1:9100`.
+    pub listen: Option<SocketAddr>,
+    /// A token that must be sent as `Authorization: Bearer <token>`
+    /// to get the metrics. Required to serve them on port 3000.
+    pub token: Option<String>,
+}
+
+/// How many levels each IP adress and author brand may upload. See
+/// [`Quota`](crate::utils::quota::Quota) for per-IP overrides.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Quotas {
+    /// How many levels each IP adress may upload per day.
+    pub daily_per_ip: u32,
+    /// How many levels each author brand may upload per day.
+    pub daily_per_brand: u32,
+    /// How many levels from each IP adress may exist at once.
+    pub max_live_per_ip: usize,
+}
+
+impl Default for Quotas {
+    fn default() -> Self {
+        Self {
+            daily_per_ip: 10,
+            daily_per_brand: 10,
+            max_live_per_ip: 100,
+        }
+    }
+}
+
+/// How many requests each IP adress may send, per route. See
+/// [`RateLimiter`](crate::utils::ratelimit::RateLimiter).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimits {
+    /// Uploading a level.
+    pub post: Limit,
+    /// Editing a level.
+    pub put: Limit,
+    /// Deleting a level.
+    pub delete: Limit,
+    /// Confirming an upload, see
+    /// [`orphanage`](crate::utils::routers::post::orphanage).
+    pub orphanage: Limit,
+    /// Downloading every level, and checking whether levels exist.
+    pub get: Limit,
+    /// Reporting a level.
+    pub report: Limit,
+    /// Registering and using a creator account, see
+    /// [`Creator`](crate::utils::creator::Creator).
+    pub creator: Limit,
+    /// Counting a play or a clear of a level.
+    pub stats: Limit,
+    /// Submitting a replay, see [`verify`](crate::utils::replay::verify).
+    pub replay: Limit,
+    /// Getting a level's thumbnail, see
+    /// [`thumbnail`](crate::utils::thumbnail::thumbnail).
+    pub thumbnail: Limit,
+}
+
+/// A limit on how many requests each IP adress may send.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Limit {
+    /// How many requests may be sent at once. Must not be 0.
+    pub burst: NonZeroU32,
+    /// How many more requests are allowed per minute. Must not be 0.
+    pub per_minute: NonZeroU32,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        let limit = |burst: u32, per_minute: u32| Limit {
+            burst: burst.try_into().expect("nonzero default burst"),
+            per_minute: per_minute.try_into().expect("nonzero default rate"),
+        };
+        Self {
+            post: limit(5, 10),
+            put: limit(5, 10),
+            delete: limit(5, 10),
+            orphanage: limit(5, 10),
+            get: limit(30, 60),
+            report: limit(5, 10),
+            creator: limit(5, 10),
+            stats: limit(10, 30),
+            replay: limit(5, 10),
+            thumbnail: limit(60, 120),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_revisions: 10,
+            orphan_ttl: 60 * 60 * 24,
+            tombstone_ttl: 60 * 60 * 24 * 30,
+            rate_limits: RateLimits::default(),
+            quotas: Quotas::default(),
+            duplicates: DuplicatePolicy::default(),
+            near_duplicate_similarity: 0.9,
+            report_threshold: 0,
+            shutdown_delay: 5,
+            metrics: Metrics::default(),
+            logging: Logging::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Attempts to load the settings from `voyager/config.toml`.
+    /// If it fails (likely due to it not existing), it instead
+    /// uses the default settings.
+    ///
+    /// # Panics
+    /// Panics if a config file is found, but it is not valid.
+    #[must_use]
+    pub fn load() -> Self {
+        read_to_string("voyager/config.toml").map_or_else(
+            |_| {
+                info!("Existing config not found, using defaults!");
+                Self::default()
+            },
+            |config| {
+                info!("Existing config found!");
+                toml::from_str(&config).expect("valid config file")
+            },
+        )
+    }
+}
@@ -0,0 +1,117 @@
+//! Contains [`Creator`], an optional account tied to an author
+//! brand, [`credentials`], which reads a creator's credentials
+//! from a request, and [`authenticate`], which checks them.
+
+use crate::prelude::*;
+use crate::utils::level::{Author, Brand};
+use axum::http::{header::AUTHORIZATION, HeaderMap};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use password_auth::{generate_hash, verify_password};
+use serde::{Deserialize, Serialize};
+
+/// A creator secret's min length.
+pub const MIN_SECRET_LEN: usize = 8;
+
+/// A creator secret's max length.
+pub const MAX_SECRET_LEN: usize = 128;
+
+/// A creator account, registered for an author brand and name.
+///
+/// Levels uploaded with the creator's credentials (see [`credentials`])
+/// belong to the creator, who may then list them, get new keys for
+/// them, rename themselves across them, and delete them, even after
+/// losing the levels' keys.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Creator {
+    /// The creator's author brand.
+    pub brand: Brand,
+    /// The creator's author name.
+    pub author: Author,
+    /// The hash of the creator's secret. The secret itself is never stored.
+    secret_hash: String,
+    /// When the creator registered, in `yyyymmdd` format.
+    pub registered: String,
+}
+
+impl Creator {
+    /// Creates a new creator account, hashing the secret.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidSecret`] if the secret is
+    /// shorter than [`MIN_SECRET_LEN`] or longer than [`MAX_SECRET_LEN`].
+    pub fn new(brand: Brand, author: Author, secret: &str) -> Result<Self> {
+        if !(MIN_SECRET_LEN..=MAX_SECRET_LEN).contains(&secret.len()) {
+            return Err(Error::InvalidSecret);
+        }
+        Ok(Self {
+            brand,
+            author,
+            secret_hash: generate_hash(secret),
+            registered: crate::utils::level::today(),
+        })
+    }
+
+    /// Checks whether the secret is the creator's.
+    #[must_use]
+    pub fn verify(&self, secret: &str) -> bool {
+        verify_password(secret, &self.secret_hash).is_ok()
+    }
+}
+
+/// Reads a creator's brand and secret from a request's `Authorization`
+/// header, using HTTP Basic authentication with the brand as the
+/// username and the secret as the password, e.g. `2685020332:hunter22`.
+///
+/// Returns `None` if the request has no `Authorization` header.
+///
+/// # Errors
+/// Returns [`Error::Unauthorized`] if the header is malformed,
+/// or an error if the brand is invalid.
+pub fn credentials(headers: &HeaderMap) -> Result<Option<(Brand, String)>> {
+    let Some(header) = headers.get(AUTHORIZATION) else {
+        return Ok(None);
+    };
+    let encoded = header
+        .to_str()
+        .ok()
+        .and_then(|header| header.strip_prefix("Basic "))
+        .ok_or(Error::Unauthorized)?;
+    let decoded = BASE64_STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .ok_or(Error::Unauthorized)?;
+    let (brand, secret) = decoded.split_once(':').ok_or(Error::Unauthorized)?;
+    Ok(Some((Brand::try_from(brand)?, secret.to_string())))
+}
+
+/// Returns the creator whose credentials were sent (see [`credentials`]),
+/// or `None` if the request has no credentials. See [`AppState::authenticate`].
+///
+/// Checking a secret is deliberately slow, so it runs on a blocking thread.
+///
+/// # Errors
+/// Returns [`Error::Unauthorized`] if the credentials are malformed
+/// or wrong, or an error if the brand is invalid.
+pub async fn authenticate(db: &SharedAppState, headers: &HeaderMap) -> Result<Option<Creator>> {
+    let Some((brand, secret)) = credentials(headers)? else {
+        return Ok(None);
+    };
+    let db = db.clone();
+    blocking(move || db.authenticate(brand, &secret))
+        .await
+        .map(Some)
+}
+
+/// Runs slow work, such as hashing a secret, on a blocking
+/// thread instead of holding up the async runtime.
+///
+/// # Errors
+/// Returns the work's error, or [`Error::Io`] if it panicked.
+pub async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(std::io::Error::other)?
+}
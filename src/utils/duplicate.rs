@@ -0,0 +1,115 @@
+//! Contains [`Fingerprint`], a level's content fingerprint,
+//! and [`Content`], a level's decoded tiles and objects, used
+//! to find duplicate and near-duplicate levels.
+//!
+//! Two levels are exact duplicates if their tiles and objects decode
+//! into the same cells, no matter their name, author, or how the
+//! cells were written (e.g. `ptX1` and `ptpt` are the same). Two
+//! levels are near-duplicates if most of their cells are the same,
+//! see [`Content::similarity`].
+
+use crate::prelude::*;
+use crate::utils::grid::Cell;
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// What to do with an uploaded (or edited) level that
+/// is an exact duplicate of an existing one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// Refuse the upload (or edit) with [`Error::Duplicate`].
+    #[default]
+    Reject,
+    /// Accept the upload, and list it as a possible
+    /// duplicate in the Web UI.
+    Flag,
+}
+
+/// A level's content fingerprint, the first 16 hexadecimal digits
+/// of the SHA-256 hash of its canonical tiles and objects.
+///
+/// Levels whose tiles or objects cannot be decoded are instead
+/// fingerprinted as written.
+#[derive(Debug, Display, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Fingerprint(String);
+
+/// A level's tiles and objects, decoded into cells.
+#[derive(Debug, Clone)]
+pub struct Content {
+    /// The level's public ID.
+    pub id: PublicId,
+    /// Every tile, followed by every object, or `None` if
+    /// the tiles or objects could not be decoded.
+    cells: Option<Vec<Cell>>,
+}
+
+impl Fingerprint {
+    /// Computes a level's fingerprint.
+    #[must_use]
+    pub fn new(level: &Parsed) -> Self {
+        let canonical = match (level.tiles.decode(), level.objects.decode()) {
+            (Some(tiles), Some(objects)) => [tiles, objects]
+                .iter()
+                .map(|grid| {
+                    grid.cells()
+                        .map(|(_, _, cell)| cell.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .collect::<Vec<String>>()
+                .join(";"),
+            _ => format!("{}|{}", level.tiles, level.objects),
+        };
+        let hash = Sha256::digest(canonical.as_bytes());
+        Self(format!("{hash:x}")[..16].to_string())
+    }
+}
+
+impl Content {
+    /// Decodes a level's tiles and objects.
+    #[must_use]
+    pub fn new(level: &Parsed) -> Self {
+        let cells = level
+            .tiles
+            .decode()
+            .zip(level.objects.decode())
+            .map(|(tiles, objects)| {
+                tiles
+                    .cells()
+                    .chain(objects.cells())
+                    .map(|(_, _, cell)| cell.clone())
+                    .collect()
+            });
+        Self {
+            id: level.id,
+            cells,
+        }
+    }
+
+    /// How many cells the level has, or `None` if its
+    /// tiles or objects could not be decoded.
+    #[must_use]
+    pub fn cell_count(&self) -> Option<usize> {
+        self.cells.as_ref().map(Vec::len)
+    }
+
+    /// How similar two levels are, between 0 and 1: the ratio of
+    /// cells that are the same in both.
+    ///
+    /// Levels of different sizes, or which could not be
+    /// decoded, have a similarity of 0.
+    #[must_use]
+    // precision loss only matters past 2^52 cells
+    #[allow(clippy::cast_precision_loss)]
+    pub fn similarity(&self, other: &Self) -> f64 {
+        match (&self.cells, &other.cells) {
+            (Some(cells), Some(others)) if cells.len() == others.len() && !cells.is_empty() => {
+                let same = cells.iter().zip(others).filter(|(a, b)| a == b).count();
+                same as f64 / cells.len() as f64
+            }
+            _ => 0.0,
+        }
+    }
+}
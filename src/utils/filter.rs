@@ -0,0 +1,265 @@
+//! Contains [`WordList`], the content filter for level
+//! names, descriptions, and authors.
+//!
+//! The word list is read from `voyager/wordlist.txt`, one word per
+//! line, optionally followed by what to do with text containing it
+//! (see [`Action`], defaulting to `reject`). Empty lines and lines
+//! starting with `#` are ignored. For example:
+//!
+//! ```text
+//! # slurs
+//! badword reject
+//! spamword hold
+//! rudeword mask
+//! *spam* hold
+//! ```
+//!
+//! Words only match whole words in the text, so `ass` does not match
+//! `class`. A `*` at the start or end of a word lets it match anywhere
+//! in a word instead: `*spam*` matches `antispammer`, `spam*` only
+//! matches words starting with `spam`.
+//!
+//! Both the words and the text are normalized before matching: case
+//! folded, stripped of diacritics and zero-width characters, and with
+//! leetspeak undone (e.g. `B4DW0RD` matches `badword`).
+
+use crate::prelude::*;
+use std::fs::read_to_string;
+use tracing::{info, warn};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Characters which are invisible, and therefore ignored.
+const ZERO_WIDTH: [char; 5] = ['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}'];
+
+/// What to do with text containing a word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Refuse the level with [`Error::BlockedWord`].
+    Reject,
+    /// Accept the level, but hold it for review in the Web UI
+    /// instead of making it public.
+    Hold,
+    /// Accept the level, replacing the word with asterisks.
+    Mask,
+}
+
+/// A single word in the [`WordList`].
+#[derive(Debug, Clone)]
+struct Entry {
+    /// The word, normalized.
+    word: Vec<char>,
+    /// Whether the word may match in the middle of a word (`*word`).
+    any_start: bool,
+    /// Whether the word may match in the middle of a word (`word*`).
+    any_end: bool,
+    action: Action,
+}
+
+/// The content filter. See the module documentation for details.
+#[derive(Debug, Default)]
+pub struct WordList {
+    entries: Vec<Entry>,
+}
+
+impl WordList {
+    /// Attempts to load the word list from `voyager/wordlist.txt`.
+    /// If it fails (likely due to it not existing), nothing is
+    /// filtered. Invalid lines are skipped with a warning.
+    #[must_use]
+    pub fn load() -> Self {
+        let Ok(input) = read_to_string("voyager/wordlist.txt") else {
+            info!("Existing word list not found, filtering nothing!");
+            return Self::default();
+        };
+        let words = Self::parse(&input);
+        info!("Loaded word list with {} entries!", words.count());
+        words
+    }
+
+    /// Parses a word list, see the module documentation.
+    /// Invalid lines are skipped with a warning.
+    fn parse(input: &str) -> Self {
+        let entries = input
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (word, action) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+                let action = match action.trim() {
+                    "" | "reject" => Action::Reject,
+                    "hold" => Action::Hold,
+                    "mask" => Action::Mask,
+                    other => {
+                        warn!("Skipping word list entry {word}: unknown action {other}");
+                        return None;
+                    }
+                };
+                let (word, any_start) = word
+                    .strip_prefix('*')
+                    .map_or((word, false), |word| (word, true));
+                let (word, any_end) = word
+                    .strip_suffix('*')
+                    .map_or((word, false), |word| (word, true));
+                let word = normalize(word).into_iter().map(|(char, _)| char).collect();
+                Some(Entry {
+                    word,
+                    any_start,
+                    any_end,
+                    action,
+                })
+            })
+            .collect::<Vec<Entry>>();
+        Self { entries }
+    }
+
+    /// How many words are in the word list.
+    #[must_use]
+    pub const fn count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Filters a level's text field, masking words if needed.
+    /// Returns whether the level should be held for review.
+    ///
+    /// # Errors
+    /// Returns [`Error::BlockedWord`] if the text contains
+    /// a word which should be rejected.
+    pub fn apply(&self, field: &'static str, text: &mut String) -> Result<bool> {
+        let normalized = normalize(text);
+        let chars = normalized
+            .iter()
+            .map(|(char, _)| *char)
+            .collect::<Vec<char>>();
+        let mut held = false;
+        let mut masked = Vec::new();
+        for entry in self.entries.iter().filter(|entry| !entry.word.is_empty()) {
+            let length = entry.word.len();
+            for start in entry.matches(&chars) {
+                match entry.action {
+                    Action::Reject => return Err(Error::BlockedWord(field)),
+                    Action::Hold => held = true,
+                    Action::Mask => {
+                        masked.push(normalized[start].1..=normalized[start + length - 1].1);
+                    }
+                }
+            }
+        }
+        if !masked.is_empty() {
+            *text = text
+                .chars()
+                .enumerate()
+                .map(|(index, char)| {
+                    if masked.iter().any(|range| range.contains(&index)) {
+                        '*'
+                    } else {
+                        char
+                    }
+                })
+                .collect();
+        }
+        Ok(held)
+    }
+}
+
+impl Entry {
+    /// Every index at which the word matches the (normalized) text.
+    fn matches<'a>(&'a self, text: &'a [char]) -> impl Iterator<Item = usize> + 'a {
+        let length = self.word.len();
+        let in_word = |index: Option<usize>| {
+            index
+                .and_then(|index| text.get(index))
+                .is_some_and(|char| char.is_alphanumeric())
+        };
+        (0..text.len().saturating_sub(length - 1))
+            .filter(move |start| text[*start..*start + length] == self.word[..])
+            .filter(move |start| self.any_start || !in_word(start.checked_sub(1)))
+            .filter(move |start| self.any_end || !in_word(Some(start + length)))
+    }
+}
+
+/// Normalizes text for matching, see the module documentation.
+///
+/// Returns every remaining character along with its
+/// index among the original text's characters.
+fn normalize(input: &str) -> Vec<(char, usize)> {
+    input
+        .chars()
+        .enumerate()
+        .filter(|(_, char)| !ZERO_WIDTH.contains(char) && !is_combining_mark(*char))
+        .map(|(index, char)| {
+            let base = char.nfd().next().unwrap_or(char);
+            let folded = base.to_lowercase().next().unwrap_or(base);
+            let unleet = match folded {
+                '0' => 'o',
+                '1' | '!' => 'i',
+                '3' => 'e',
+                '4' | '@' => 'a',
+                '5' | '$' => 's',
+                '7' => 't',
+                other => other,
+            };
+            (unleet, index)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(words: &str, text: &str) -> Result<(bool, String)> {
+        let mut text = text.to_string();
+        let held = WordList::parse(words).apply("name", &mut text)?;
+        Ok((held, text))
+    }
+
+    fn rejects(words: &str, text: &str) -> bool {
+        matches!(apply(words, text), Err(Error::BlockedWord("name")))
+    }
+
+    fn accepts(words: &str, text: &str) -> Option<(bool, String)> {
+        apply(words, text).ok()
+    }
+
+    #[test]
+    fn matches_whole_words() {
+        assert!(rejects("ass", "ass"));
+        assert!(rejects("ass", "what an ass."));
+        assert!(rejects("ass", "(ass)"));
+        assert!(!rejects("ass", "class"));
+        assert!(!rejects("ass", "assassin"));
+        assert!(!rejects("ass", "bass guitar"));
+    }
+
+    #[test]
+    fn stars_match_inside_words() {
+        assert!(rejects("*spam*", "antispammer"));
+        assert!(rejects("spam*", "spammer"));
+        assert!(!rejects("spam*", "antispam"));
+        assert!(rejects("*spam", "antispam"));
+        assert!(!rejects("*spam", "spammer"));
+    }
+
+    #[test]
+    fn normalizes_text() {
+        assert!(rejects("badword", "B4DW0RD"));
+        assert!(rejects("badword", "bád\u{200B}word"));
+        assert!(rejects("b4dword", "badword"));
+    }
+
+    #[test]
+    fn applies_actions() {
+        let words = "badword\nspamword hold\nrudeword mask\n# comment\nnope maybe";
+        assert!(rejects(words, "a badword"));
+        assert_eq!(
+            accepts(words, "spamword"),
+            Some((true, "spamword".to_string()))
+        );
+        assert_eq!(
+            accepts(words, "you rüdeword, rudeword"),
+            Some((false, "you ********, ********".to_string()))
+        );
+        assert_eq!(accepts(words, "fine"), Some((false, "fine".to_string())));
+        assert_eq!(WordList::parse(words).count(), 3);
+    }
+}
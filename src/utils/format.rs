@@ -0,0 +1,175 @@
+//! Contains [`Format`], the registry of level format versions.
+//!
+//! A level's data starts with its format version (see [`Data`]), which
+//! picks the [`Format`] parsing the rest of it. Every format parses into
+//! the same [`Parsed`] level, so that the rest of Voyager does not care
+//! which version a level was uploaded in. Levels are stored in the version
+//! they were uploaded in, and may be listed in any other version (see
+//! [`Format::represents`]).
+//!
+//! Adding a version means adding a variant to [`Format`] (and to
+//! [`Format::ALL`]), a parser, and an encoder. Fields that only newer
+//! versions have should be optional in [`Parsed`], and older versions
+//! should only represent levels that do not use them.
+
+// for documentation
+#[allow(unused_imports)]
+use crate::utils::level::Data;
+use crate::{
+    prelude::*,
+    utils::level::{
+        Author, Brand, Burdens, Description, Edited, Music, Name, Objects, Tiles, Uploaded, Version,
+    },
+};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use itertools::Itertools;
+
+/// A level format version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `version|name|description|music|author|brand|uploaded|edited|burdens|tiles|objects`,
+    /// with the dates left empty when uploading, a key appended when
+    /// editing, and the public ID appended in a versioned listing.
+    /// See [`Data`].
+    V1,
+}
+
+impl Format {
+    /// Every supported format, oldest first.
+    pub const ALL: [Self; 1] = [Self::V1];
+
+    /// The newest supported format.
+    pub const LATEST: Self = Self::V1;
+
+    /// The format's version number, the first field of a level's data.
+    #[must_use]
+    pub const fn version(self) -> u8 {
+        match self {
+            Self::V1 => 1,
+        }
+    }
+
+    /// Looks up a format by its version number.
+    #[must_use]
+    pub fn from_version(version: u8) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.version() == version)
+    }
+
+    /// Parses and validates a level's fields (everything after the
+    /// version), running every field's validation even after one
+    /// has failed.
+    ///
+    /// # Errors
+    /// Returns every field's error (at least one) in field order if
+    /// any field was invalid, or only [`Error::InvalidStructure`]
+    /// if the fields could not be split.
+    pub fn parse<State>(
+        self,
+        version: Version,
+        fields: &str,
+        level: &Level<State>,
+    ) -> std::result::Result<Parsed, Vec<Error>> {
+        match self {
+            Self::V1 => parse_v1(version, fields, level),
+        }
+    }
+
+    /// Whether the level can be encoded in this format without losing
+    /// anything. If not, the level is left out of listings in this format.
+    #[must_use]
+    pub const fn represents(self, _level: &Parsed) -> bool {
+        match self {
+            Self::V1 => true,
+        }
+    }
+
+    /// Encodes a level's data in this format. Should only be
+    /// used if the format [represents](Self::represents) it.
+    #[must_use]
+    pub fn encode(self, level: &Parsed) -> String {
+        match self {
+            Self::V1 => encode_v1(level),
+        }
+    }
+}
+
+/// Parses a version 1 level, see [`Format::V1`].
+fn parse_v1<State>(
+    version: Version,
+    fields: &str,
+    level: &Level<State>,
+) -> std::result::Result<Parsed, Vec<Error>> {
+    let Some((name, description, music, author, brand, uploaded, edited, burdens, tiles, objects)) =
+        fields.splitn(10, '|').collect_tuple()
+    else {
+        return Err(vec![Error::InvalidStructure]);
+    };
+
+    let mut errors = Vec::new();
+    let name = keep(Name::try_from(name), &mut errors);
+    let description = keep(Description::try_from(description), &mut errors);
+    let music = keep(Music::try_from(music), &mut errors);
+    let author = keep(Author::try_from(author), &mut errors);
+    let brand = keep(Brand::try_from(brand), &mut errors);
+    let burdens = keep(Burdens::try_from(burdens), &mut errors);
+    let tiles = keep(Tiles::try_from(tiles), &mut errors);
+    let objects = keep(Objects::try_from(objects), &mut errors);
+
+    let (
+        Some(name),
+        Some(description),
+        Some(music),
+        Some(author),
+        Some(brand),
+        Some(burdens),
+        Some(tiles),
+        Some(objects),
+    ) = (
+        name,
+        description,
+        music,
+        author,
+        brand,
+        burdens,
+        tiles,
+        objects,
+    )
+    else {
+        return Err(errors);
+    };
+    Ok(Parsed {
+        version,
+        name,
+        description,
+        music,
+        author,
+        brand,
+        uploaded: Uploaded::from(uploaded),
+        edited: Edited::from(edited),
+        burdens,
+        tiles,
+        objects,
+        id: level.id,
+        key_hash: level.key_hash,
+        uploader: level.uploader,
+    })
+}
+
+/// Encodes a level as version 1, see [`Format::V1`].
+fn encode_v1(level: &Parsed) -> String {
+    let name = BASE64_STANDARD.encode(level.name.to_string());
+    let description = BASE64_STANDARD.encode(level.description.to_string());
+    let music = BASE64_STANDARD.encode(level.music.to_string());
+    let author = BASE64_STANDARD.encode(level.author.to_string());
+    format!(
+        "1|{name}|{description}|{music}|{author}|{}|{}|{}|{}|{}|{}",
+        level.brand, level.uploaded, level.edited, level.burdens, level.tiles, level.objects
+    )
+}
+
+/// Keeps a field's value if it was valid, or its error otherwise.
+fn keep<T>(field: Result<T>, errors: &mut Vec<Error>) -> Option<T> {
+    field.map_err(|why| errors.push(why)).ok()
+}
@@ -0,0 +1,156 @@
+//! Contains [`Grid`], a level's tiles or objects decoded
+//! from Endless Void's black hole format.
+//!
+//! Each cell is written as a two-letter id (e.g. `fl` for floor),
+//! optionally followed by a number picking a variant (e.g. `wa16`).
+//! `X` followed by a number repeats the previous cell that many more
+//! times, so `ptX3` is four pits in a row. Cells are laid out row by
+//! row, [`GRID_WIDTH`] cells per row.
+
+// for documentation
+#[allow(unused_imports)]
+use crate::utils::level::BLACK_HOLE_FORMAT;
+use derive_more::Display;
+
+/// How many cells wide a level is.
+pub const GRID_WIDTH: usize = 14;
+
+/// The most cells a grid may decode into. Void Stranger rooms
+/// are far smaller, this only guards against huge repeats.
+pub const MAX_GRID_CELLS: usize = GRID_WIDTH * 16;
+
+/// A single decoded cell, such as `fl` or `wa16`.
+#[derive(Debug, Display, Clone, PartialEq, Eq, Hash)]
+pub struct Cell(String);
+
+/// A level's tiles or objects, decoded into cells.
+///
+/// See the module documentation for details on the format.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid {
+    cells: Vec<Cell>,
+}
+
+impl Cell {
+    /// The cell's two-letter id, without its variant.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.0[..2]
+    }
+
+    /// Creates a cell with no variant from a two-letter id.
+    pub(crate) fn new(id: &str) -> Self {
+        Self(id.to_string())
+    }
+}
+
+impl Grid {
+    /// Decodes tiles or objects in black hole format.
+    ///
+    /// Returns `None` if the input contains anything but cells
+    /// and repeats (even if it is allowed by [`BLACK_HOLE_FORMAT`]),
+    /// if it does not fill up a whole number of rows, or if it
+    /// has more than [`MAX_GRID_CELLS`] cells.
+    #[must_use]
+    pub fn decode(input: &str) -> Option<Self> {
+        let mut cells: Vec<Cell> = Vec::new();
+        let mut chars = input.chars().peekable();
+        while let Some(char) = chars.next() {
+            if char == 'X' {
+                let mut count = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    count.push(digit);
+                }
+                let count = count.parse::<usize>().ok()?;
+                // the count comes from the client, so it may be huge
+                if count > MAX_GRID_CELLS - cells.len() {
+                    return None;
+                }
+                let previous = cells.last()?.clone();
+                cells.extend(std::iter::repeat_n(previous, count));
+            } else if char.is_ascii_lowercase() {
+                let mut cell = String::from(char);
+                cell.push(chars.next().filter(char::is_ascii_lowercase)?);
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    cell.push(digit);
+                }
+                if cells.len() == MAX_GRID_CELLS {
+                    return None;
+                }
+                cells.push(Cell(cell));
+            } else {
+                return None;
+            }
+        }
+        if cells.is_empty() || !cells.len().is_multiple_of(GRID_WIDTH) {
+            return None;
+        }
+        Some(Self { cells })
+    }
+
+    /// The cell at the given column and row, if it is inside the grid.
+    #[must_use]
+    pub fn get(&self, x: usize, y: usize) -> Option<&Cell> {
+        if x >= GRID_WIDTH {
+            return None;
+        }
+        self.cells.get(y * GRID_WIDTH + x)
+    }
+
+    /// Replaces the cell at the given column and row,
+    /// if it is inside the grid.
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        if x < GRID_WIDTH {
+            if let Some(old) = self.cells.get_mut(y * GRID_WIDTH + x) {
+                *old = cell;
+            }
+        }
+    }
+
+    /// How many rows the grid has.
+    #[must_use]
+    pub const fn rows(&self) -> usize {
+        self.cells.len() / GRID_WIDTH
+    }
+
+    /// Every cell along with its column and row, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, &Cell)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| (index % GRID_WIDTH, index / GRID_WIDTH, cell))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_cells_and_repeats() {
+        let grid = Grid::decode("wa16flX11ex").expect("valid grid");
+        assert_eq!(grid.rows(), 1);
+        assert_eq!(grid.get(0, 0), Some(&Cell("wa16".to_string())));
+        assert_eq!(grid.get(12, 0), Some(&Cell::new("fl")));
+        assert_eq!(grid.get(13, 0), Some(&Cell::new("ex")));
+        assert_eq!(grid.get(14, 0), None);
+    }
+
+    #[test]
+    fn rejects_invalid_grids() {
+        assert_eq!(Grid::decode(""), None);
+        assert_eq!(Grid::decode("flX12"), None);
+        assert_eq!(Grid::decode("X13"), None);
+        assert_eq!(Grid::decode("flX12E"), None);
+    }
+
+    #[test]
+    fn rejects_huge_repeats() {
+        assert!(Grid::decode(&format!("flX{}", MAX_GRID_CELLS - 1)).is_some());
+        assert_eq!(Grid::decode(&format!("flX{MAX_GRID_CELLS}")), None);
+        assert_eq!(Grid::decode(&format!("flX{}", usize::MAX)), None);
+        assert_eq!(Grid::decode(&format!("flflX{}", usize::MAX)), None);
+        // too big to even be a count
+        assert_eq!(Grid::decode(&format!("flX{}0", usize::MAX)), None);
+    }
+}
@@ -0,0 +1,96 @@
+//! Contains [`Health`], what the readiness check (see
+//! [`readyz`](crate::utils::routers::health::readyz)) looks at.
+
+use std::{
+    fs::{remove_file, write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+use ulid::Ulid;
+
+/// The server's health, only kept in memory.
+#[derive(Debug, Default)]
+pub struct Health {
+    /// Whether the database was loaded (or created) on startup.
+    loaded: AtomicBool,
+    /// Why the latest save failed, or `None` if it succeeded
+    /// (or there has not been one yet).
+    save_error: Mutex<Option<String>>,
+    /// Whether the server is shutting down.
+    shutting_down: AtomicBool,
+    /// When the `voyager` directory was last checked, and
+    /// whether it was writable, see [`Health::data_dir_writable`].
+    writable: Mutex<Option<(Instant, bool)>>,
+}
+
+/// How long [`Health::data_dir_writable`] trusts its latest check.
+const WRITABLE_TTL: Duration = Duration::from_secs(5);
+
+impl Health {
+    /// Marks the database as loaded.
+    pub fn set_loaded(&self) {
+        self.loaded.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the database was loaded.
+    #[must_use]
+    pub fn loaded(&self) -> bool {
+        self.loaded.load(Ordering::Relaxed)
+    }
+
+    /// Records how the latest save went.
+    pub fn record_save(&self, error: Option<String>) {
+        if let Ok(mut save_error) = self.save_error.lock() {
+            *save_error = error;
+        }
+    }
+
+    /// Why the latest save failed, if it did.
+    #[must_use]
+    pub fn save_error(&self) -> Option<String> {
+        self.save_error.lock().ok().and_then(|error| error.clone())
+    }
+
+    /// Marks the server as shutting down, so that it is no longer ready.
+    pub fn set_shutting_down(&self) {
+        self.shutting_down.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the server is shutting down.
+    #[must_use]
+    pub fn shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::Relaxed)
+    }
+
+    /// Whether the `voyager` directory is writable. The directory
+    /// is only checked again once the latest check is older than
+    /// [`WRITABLE_TTL`], so that probing readiness does not touch
+    /// the disk on every request.
+    #[must_use]
+    pub fn data_dir_writable(&self) -> bool {
+        let mut writable = self
+            .writable
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        match *writable {
+            Some((checked, result)) if checked.elapsed() < WRITABLE_TTL => result,
+            _ => {
+                let result = probe_data_dir();
+                *writable = Some((Instant::now(), result));
+                result
+            }
+        }
+    }
+}
+
+/// Checks whether the `voyager` directory is writable,
+/// by writing and removing an empty file in it. Every
+/// check uses its own file, as checks may run at once.
+#[must_use]
+fn probe_data_dir() -> bool {
+    let probe = format!("voyager/.readyz-{}", Ulid::new());
+    write(&probe, []).is_ok() && remove_file(&probe).is_ok()
+}
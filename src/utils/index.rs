@@ -0,0 +1,173 @@
+//! Contains [`Indexes`], secondary indexes over the levels,
+//! and [`Filter`], a query against them.
+
+use crate::prelude::*;
+use crate::utils::{
+    duplicate::Fingerprint,
+    level::{Brand, KeyHash, Validated},
+};
+use dashmap::DashMap;
+use serde::Deserialize;
+use std::{collections::HashSet, hash::Hash, net::IpAddr};
+
+/// Secondary indexes over the levels. Each one maps a field's
+/// value to the public IDs of every level with that value.
+///
+/// Kept up to date by [`AppState`](crate::utils::server::AppState)
+/// on every insert, edit, and delete, and rebuilt on load.
+#[derive(Debug, Default)]
+pub struct Indexes {
+    /// Key hash to public ID.
+    key: DashMap<KeyHash, PublicId>,
+    /// Uploader IP adress to public IDs.
+    uploader: DashMap<IpAddr, HashSet<PublicId>>,
+    /// Author brand to public IDs.
+    brand: DashMap<Brand, HashSet<PublicId>>,
+    /// Normalized author name (see [`normalize_author`]) to public IDs.
+    author: DashMap<String, HashSet<PublicId>>,
+    /// Upload date in `yyyymmdd` format to public IDs.
+    uploaded: DashMap<String, HashSet<PublicId>>,
+    /// Content fingerprint to public IDs.
+    fingerprint: DashMap<Fingerprint, HashSet<PublicId>>,
+}
+
+/// A query against the [`Indexes`]. Every given field must match.
+///
+/// Empty fields are ignored, so that it may be taken directly
+/// from an HTML form.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Filter {
+    /// The uploader's IP adress.
+    pub uploader: Option<String>,
+    /// The author brand, as a base-10 integer.
+    pub brand: Option<String>,
+    /// The author name, matched after [`normalize_author`].
+    pub author: Option<String>,
+    /// The upload date, in `yyyymmdd` format.
+    pub uploaded: Option<String>,
+}
+
+/// Normalizes an author name for lookups: trimmed, lowercase,
+/// and with runs of whitespace collapsed into a single space.
+#[must_use]
+pub fn normalize_author(input: &str) -> String {
+    input
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+impl Indexes {
+    /// Adds a level to every index.
+    pub fn add(&self, level: &Level<Validated>) {
+        let id = level.id;
+        self.key.insert(level.key_hash, id);
+        self.uploader.entry(level.uploader).or_default().insert(id);
+        if let Ok(parsed) = level.clone().into_parsed() {
+            self.brand.entry(parsed.brand).or_default().insert(id);
+            self.author
+                .entry(normalize_author(&parsed.author.to_string()))
+                .or_default()
+                .insert(id);
+            self.uploaded
+                .entry(parsed.uploaded.to_string())
+                .or_default()
+                .insert(id);
+            self.fingerprint
+                .entry(Fingerprint::new(&parsed))
+                .or_default()
+                .insert(id);
+        }
+    }
+
+    /// Removes a level from every index.
+    pub fn remove(&self, level: &Level<Validated>) {
+        let id = level.id;
+        self.key.remove_if(&level.key_hash, |_, found| *found == id);
+        Self::remove_from(&self.uploader, &level.uploader, id);
+        if let Ok(parsed) = level.clone().into_parsed() {
+            Self::remove_from(&self.brand, &parsed.brand, id);
+            Self::remove_from(
+                &self.author,
+                &normalize_author(&parsed.author.to_string()),
+                id,
+            );
+            Self::remove_from(&self.uploaded, &parsed.uploaded.to_string(), id);
+            Self::remove_from(&self.fingerprint, &Fingerprint::new(&parsed), id);
+        }
+    }
+
+    /// Returns the public ID of the level with the key hash, if any.
+    #[must_use]
+    pub fn by_key(&self, input: &KeyHash) -> Option<PublicId> {
+        self.key.get(input).map(|id| *id)
+    }
+
+    /// Returns the public IDs of every level uploaded by the IP adress.
+    #[must_use]
+    pub fn by_uploader(&self, input: &IpAddr) -> HashSet<PublicId> {
+        Self::lookup(&self.uploader, input)
+    }
+
+    /// Returns the public IDs of every level with the author brand.
+    #[must_use]
+    pub fn by_brand(&self, input: Brand) -> HashSet<PublicId> {
+        Self::lookup(&self.brand, &input)
+    }
+
+    /// Returns the public IDs of every level with the content fingerprint.
+    #[must_use]
+    pub fn by_fingerprint(&self, input: &Fingerprint) -> HashSet<PublicId> {
+        Self::lookup(&self.fingerprint, input)
+    }
+
+    /// Returns every group of two or more levels sharing a
+    /// content fingerprint, i.e. every group of exact duplicates.
+    #[must_use]
+    pub fn duplicate_groups(&self) -> Vec<HashSet<PublicId>> {
+        self.fingerprint
+            .iter()
+            .filter(|ids| ids.len() > 1)
+            .map(|ids| ids.clone())
+            .collect()
+    }
+
+    /// Returns the public IDs of every level matching the filter,
+    /// or `None` if the filter is empty (everything matches).
+    ///
+    /// # Errors
+    /// Returns an error if the filter's uploader or brand is invalid.
+    pub fn find(&self, filter: &Filter) -> Result<Option<HashSet<PublicId>>> {
+        let given = |field: &Option<String>| field.clone().filter(|field| !field.is_empty());
+        let mut matches: Vec<HashSet<PublicId>> = Vec::new();
+        if let Some(uploader) = given(&filter.uploader) {
+            matches.push(self.by_uploader(&uploader.parse()?));
+        }
+        if let Some(brand) = given(&filter.brand) {
+            matches.push(self.by_brand(Brand::try_from(brand.as_str())?));
+        }
+        if let Some(author) = given(&filter.author) {
+            matches.push(Self::lookup(&self.author, &normalize_author(&author)));
+        }
+        if let Some(uploaded) = given(&filter.uploaded) {
+            matches.push(Self::lookup(&self.uploaded, &uploaded));
+        }
+        Ok(matches.into_iter().reduce(|all, next| &all & &next))
+    }
+
+    fn lookup<T: Eq + Hash>(index: &DashMap<T, HashSet<PublicId>>, input: &T) -> HashSet<PublicId> {
+        index.get(input).map(|ids| ids.clone()).unwrap_or_default()
+    }
+
+    fn remove_from<T: Eq + Hash + Clone>(
+        index: &DashMap<T, HashSet<PublicId>>,
+        input: &T,
+        id: PublicId,
+    ) {
+        index.remove_if_mut(input, |_, ids| {
+            ids.remove(&id);
+            ids.is_empty()
+        });
+    }
+}
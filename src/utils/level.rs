@@ -2,10 +2,11 @@
 //! constants, and related wrapper types for `ParsedLevel`.
 
 use crate::prelude::*;
+use crate::utils::{filter::WordList, format::Format, grid::Grid};
 use base64::{prelude::BASE64_STANDARD, Engine};
 use derive_more::Display;
-use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{marker::PhantomData, net::IpAddr, str::FromStr};
 use time::OffsetDateTime;
 use ulid::Ulid;
@@ -76,9 +77,19 @@ pub const VALID_MUSIC: [&str; 13] = [
 ///
 /// `version|name|description|music|author|brand|||burdens|tiles|objects|key`
 ///
+/// Levels in a listing asked for in a specific version (see
+/// [`Format`]) instead have their public ID appended:
+///
+/// `1|Zm9v|YmFy|bXNjXzAwMQ==|aGV4ZmFl|2685020332|20240304|20240304|0|ptX33exptX11flX2ptX10flX2ptX10flX2ptX33|emX61plemX62|4f0a6c1be2d93875`
+///
+/// `version|name|description|music|author|brand|uploaded|edited|burdens|tiles|objects|id`
+///
+/// These are all in version 1 of the format, see [`Format::V1`].
+/// The version picks how the rest of the data is parsed.
+///
 /// See [`Version`], [`Name`], [`Description`], [`Music`],
 /// [`Author`], [`Brand`], [`Uploaded`], [`Edited`], [`Burdens`],
-/// [`Tiles`], [`Objects`], and [`Key`] for further details.
+/// [`Tiles`], [`Objects`], [`Key`], and [`PublicId`] for further details.
 #[derive(Debug, Display, Clone, Serialize, Deserialize)]
 pub struct Data(String);
 
@@ -119,16 +130,29 @@ pub struct Level<State = Unvalidated> {
     ///
     /// Stored for logging and banning.
     pub uploader: IpAddr,
-    /// The level's key.
-    pub key: Key,
+    /// The level's public ID.
+    pub id: PublicId,
+    /// The hash of the level's key. The key itself is never stored.
+    pub key_hash: KeyHash,
     /// The level's current validity state. See [`Validated`] and [`Unvalidated`].
     state: PhantomData<State>,
 }
 
-/// The level format version.
+/// A level as stored by Voyager 0.5.1 and earlier, with its
+/// key instead of a public ID and key hash.
 ///
-/// Currently, this is only 1.
+/// Only used to migrate an old database on load.
+#[derive(Deserialize)]
+pub struct LegacyLevel {
+    data: Data,
+    uploader: IpAddr,
+    key: Key,
+}
+
+/// The level format version, which picks how the rest
+/// of the level is parsed. See [`Format`].
 #[derive(Debug, Display, Clone, Serialize, Deserialize)]
+#[serde(try_from = "u8")]
 pub struct Version(u8);
 
 /// The level's name.
@@ -167,7 +191,7 @@ pub struct Author(String);
 /// Voyager and sent to/from Endless Void as a base-10 integer.
 ///
 /// See `BRAND_36_BITS` for the biggest brand possible.
-#[derive(Debug, Display, Clone, Serialize, Deserialize)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Brand(u64);
 
 /// The level's original upload date.
@@ -213,13 +237,32 @@ pub struct Objects(String);
 
 /// The level's private key.
 ///
-/// Encoded as a [ULID](https://github.com/ulid/spec) key.
-#[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, Hash, Eq, PartialEq)]
+/// Encoded as a [ULID](https://github.com/ulid/spec) key, so
+/// keys sort by when their level was uploaded.
+#[derive(
+    Debug, Display, Clone, Copy, Serialize, Deserialize, Hash, Eq, PartialEq, Ord, PartialOrd,
+)]
 pub struct Key(Ulid);
 
+/// The SHA-256 hash of a level's [`Key`].
+///
+/// Only the hash is stored, so that keys cannot
+/// be recovered from the database.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, Eq, PartialEq)]
+pub struct KeyHash([u8; 32]);
+
+/// The level's public ID.
+///
+/// Unlike the [`Key`], it is safe to share, and is how
+/// players refer to other people's levels. Encoded as
+/// 16 hexadecimal digits, e.g. `4f0a6c1be2d93875`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct PublicId(u64);
+
 /// A parsed, validated Void Stranger level.
 ///
 /// See [`Validated`] for details on level validity.
+#[derive(Clone)]
 pub struct Parsed {
     /// See [`Version`].
     pub version: Version,
@@ -243,14 +286,28 @@ pub struct Parsed {
     pub tiles: Tiles,
     /// See [`Objects`].
     pub objects: Objects,
-    /// See [`Key`].
-    pub key: Key,
+    /// See [`PublicId`].
+    pub id: PublicId,
+    /// See [`KeyHash`].
+    pub key_hash: KeyHash,
     /// See [`Uploader`].
     pub uploader: IpAddr,
 }
 
+/// Returns today's date in `yyyymmdd` format (UTC).
+#[must_use]
+pub fn today() -> String {
+    OffsetDateTime::now_utc()
+        // 2024-02-27
+        .date()
+        .to_string()
+        // 20240227
+        .replace('-', "")
+}
+
 impl Level<Unvalidated> {
-    /// Creates a new (possibly invalid) Void Stranger level, for POST.
+    /// Creates a new (possibly invalid) Void Stranger level, for POST,
+    /// along with its newly generated key.
     ///
     /// See [`Data`] for details on valid POST input.
     ///
@@ -258,13 +315,16 @@ impl Level<Unvalidated> {
     /// the level should be parsed (validated) using
     /// [`Self::into_parsed`] before insertion into the database.
     /// See [`Data`] for details on validity.
-    pub fn new(data: String, ip: IpAddr) -> Self {
-        Self {
+    pub fn new(data: String, ip: IpAddr) -> (Self, Key) {
+        let key = Key::generate();
+        let level = Self {
             data: Data(data),
             uploader: ip,
-            key: Key::new(),
+            id: PublicId::new(),
+            key_hash: key.hash(),
             state: PhantomData::<Unvalidated>,
-        }
+        };
+        (level, key)
     }
 
     /// Creates a new (possibly invalid) Void Stranger level, for PUT.
@@ -275,68 +335,83 @@ impl Level<Unvalidated> {
     /// the level should be parsed (validated) using
     /// [`Self::into_parsed`] before insertion into the database.
     /// See [`Data`] for details on validity.
-    pub fn new_from_put(input: &str, ip: IpAddr) -> Result<Self> {
+    ///
+    /// The level is given the public ID of whichever
+    /// level `id_of` finds for the key.
+    pub fn new_from_put(
+        input: &str,
+        ip: IpAddr,
+        id_of: impl FnOnce(&Key) -> Result<PublicId>,
+    ) -> Result<Self> {
         let (input, key) = input.rsplit_once('|').ok_or(Error::InvalidStructure)?;
+        let key = key.parse()?;
         Ok(Self {
             data: Data(input.into()),
             uploader: ip,
-            key: key.parse()?,
+            id: id_of(&key)?,
+            key_hash: key.hash(),
             state: PhantomData::<Unvalidated>,
         })
     }
 }
 
+impl From<LegacyLevel> for Level<Validated> {
+    fn from(legacy: LegacyLevel) -> Self {
+        let key_hash = legacy.key.hash();
+        Self {
+            data: legacy.data,
+            uploader: legacy.uploader,
+            id: PublicId::from_legacy(&key_hash),
+            key_hash,
+            state: PhantomData::<Validated>,
+        }
+    }
+}
+
+impl Level<Validated> {
+    /// The level's data followed by its public ID, as it appears
+    /// in a listing asked for in a specific version. See [`Data`].
+    #[must_use]
+    pub fn listed(&self) -> String {
+        format!("{}|{}", self.data, self.id)
+    }
+}
+
 impl<State> Level<State> {
-    /// Parses and validates the level.
+    /// The level's format version, read from its data
+    /// without parsing the rest of it.
+    #[must_use]
+    pub fn format(&self) -> Option<Format> {
+        let (version, _) = self.data.0.split_once('|')?;
+        Version::try_from(version)
+            .ok()
+            .map(|version| version.format())
+    }
+
+    /// Parses and validates the level, stopping at the first invalid
+    /// field. See [`Self::validate`] for every invalid field at once.
     pub fn into_parsed(self) -> Result<Parsed> {
-        let (
-            version,
-            name,
-            description,
-            music,
-            author,
-            brand,
-            uploaded,
-            edited,
-            burdens,
-            tiles,
-            objects,
-        ) = self
+        self.validate().map_err(|mut errors| errors.swap_remove(0))
+    }
+
+    /// Parses and validates the level in its format version (see
+    /// [`Format`]), running every field's validation even after one
+    /// has failed.
+    ///
+    /// # Errors
+    /// Returns every field's error (at least one) in field order if
+    /// any field was invalid, or only [`Error::InvalidStructure`]
+    /// if the level could not be split into its fields. If the
+    /// version is invalid, only its error is returned, as the
+    /// other fields cannot be parsed without it.
+    pub fn validate(self) -> std::result::Result<Parsed, Vec<Error>> {
+        let (version, fields) = self
             .data
             .0
-            .splitn(11, '|')
-            .collect_tuple()
-            .ok_or(Error::InvalidStructure)?;
-
-        let version = Version::try_from(version)?;
-        let name = Name::try_from(name)?;
-        let description = Description::try_from(description)?;
-        let music = Music::try_from(music)?;
-        let author = Author::try_from(author)?;
-        let brand = Brand::try_from(brand)?;
-        let uploaded = Uploaded(uploaded.to_string());
-        let edited = Edited(edited.to_string());
-        let burdens = Burdens::try_from(burdens)?;
-        let tiles = Tiles::try_from(tiles)?;
-        let objects = Objects::try_from(objects)?;
-        let key = self.key;
-        let ip = self.uploader;
-
-        Ok(Parsed {
-            version,
-            name,
-            description,
-            music,
-            author,
-            brand,
-            uploaded,
-            edited,
-            burdens,
-            tiles,
-            objects,
-            key,
-            uploader: ip,
-        })
+            .split_once('|')
+            .ok_or_else(|| vec![Error::InvalidStructure])?;
+        let version = Version::try_from(version).map_err(|why| vec![why])?;
+        version.format().parse(version, fields, &self)
     }
 }
 
@@ -344,16 +419,25 @@ impl Parsed {
     /// Sets a parsed level's upload and last
     /// edit dates to today in `yyyymmdd` format.
     pub fn set_dates_to_now(&mut self) {
-        let now = OffsetDateTime::now_utc()
-            // 2024-02-27
-            .date()
-            .to_string()
-            // 20240227
-            .replace('-', "");
-        self.uploaded.0 = now.clone();
+        let now = today();
+        self.uploaded.0.clone_from(&now);
         self.edited.0 = now;
     }
 
+    /// Runs the name, description, and author through the
+    /// word list, masking words if needed. Returns whether
+    /// the level should be held for review.
+    ///
+    /// # Errors
+    /// Returns [`Error::BlockedWord`] if a word should be rejected.
+    pub fn filter(&mut self, words: &WordList) -> Result<bool> {
+        let mut held = false;
+        held |= words.apply("name", &mut self.name.0)?;
+        held |= words.apply("description", &mut self.description.0)?;
+        held |= words.apply("author", &mut self.author.0)?;
+        Ok(held)
+    }
+
     /// Sets a parsed level's upload date from
     /// another level's upload date.
     ///
@@ -365,49 +449,104 @@ impl Parsed {
         Ok(())
     }
 
-    /// Decodes a level back into a Void Stranger level.
+    /// Decodes a level back into a Void Stranger level,
+    /// in the format version it was uploaded in.
     ///
     /// For a POST and PUT requests, this is done immediately
     /// after parsing (validating) the level to insert into
     /// the database as validated.
     pub fn into_level(self) -> Level<Validated> {
-        let version = self.version.0;
-        let name = BASE64_STANDARD.encode(self.name.0);
-        let description = BASE64_STANDARD.encode(self.description.0);
-        let music = BASE64_STANDARD.encode(self.music.0);
-        let author = BASE64_STANDARD.encode(self.author.0);
-        let brand = self.brand.0;
-        let uploaded = self.uploaded.0;
-        let edited = self.edited.0;
-        let burdens = self.burdens.0;
-        let tiles = self.tiles.0;
-        let objects = self.objects.0;
-        let data = format!("{version}|{name}|{description}|{music}|{author}|{brand}|{uploaded}|{edited}|{burdens}|{tiles}|{objects}");
+        let data = self.version.format().encode(&self);
         Level {
             data: Data(data),
-            key: self.key,
+            id: self.id,
+            key_hash: self.key_hash,
             uploader: self.uploader,
             state: PhantomData::<Validated>,
         }
     }
 }
 
+impl Tiles {
+    /// Decodes the tiles into a [`Grid`], if they can be decoded.
+    #[must_use]
+    pub fn decode(&self) -> Option<Grid> {
+        Grid::decode(&self.0)
+    }
+}
+
+impl Objects {
+    /// Decodes the objects into a [`Grid`], if they can be decoded.
+    #[must_use]
+    pub fn decode(&self) -> Option<Grid> {
+        Grid::decode(&self.0)
+    }
+}
+
 impl Key {
-    fn new() -> Self {
+    /// Generates a new key.
+    #[must_use]
+    pub fn generate() -> Self {
         Self(Ulid::new())
     }
+
+    /// The key's SHA-256 hash, which is stored instead of the key.
+    #[must_use]
+    pub fn hash(&self) -> KeyHash {
+        KeyHash(Sha256::digest(self.0.to_bytes()).into())
+    }
+}
+
+impl PublicId {
+    /// Generates a new, random public ID.
+    #[must_use]
+    pub fn new() -> Self {
+        // the low 64 bits of a ULID are random
+        #[allow(clippy::cast_possible_truncation)]
+        Self(Ulid::new().random() as u64)
+    }
+
+    /// The public ID of a level uploaded before levels had
+    /// public IDs: the first 16 hexadecimal digits of its key's
+    /// hash, which is how the level was identified until then.
+    #[must_use]
+    pub fn from_legacy(input: &KeyHash) -> Self {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&input.0[..8]);
+        Self(u64::from_be_bytes(bytes))
+    }
+}
+
+impl Default for PublicId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for PublicId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
 }
 
 impl TryFrom<&str> for Version {
     type Error = Error;
 
     fn try_from(input: &str) -> std::prelude::v1::Result<Self, Self::Error> {
-        let version = input
+        input
             .parse::<u8>()
-            .map_err(|why| Error::InvalidVersion(NumberError::NotANumber(why)))?;
-        if version != 1 {
+            .map_err(|why| Error::InvalidVersion(NumberError::NotANumber(why)))?
+            .try_into()
+    }
+}
+
+impl TryFrom<u8> for Version {
+    type Error = Error;
+
+    fn try_from(version: u8) -> std::prelude::v1::Result<Self, Self::Error> {
+        if Format::from_version(version).is_none() {
             return Err(Error::InvalidVersion(NumberError::TooBig {
-                max: 1,
+                max: u64::from(Format::LATEST.version()),
                 found: u64::from(version),
             }));
         }
@@ -415,6 +554,27 @@ impl TryFrom<&str> for Version {
     }
 }
 
+impl Version {
+    /// The level format of this version, see [`Format`].
+    #[must_use]
+    pub fn format(&self) -> Format {
+        // versions are checked when parsed (or deserialized)
+        Format::from_version(self.0).unwrap_or(Format::LATEST)
+    }
+}
+
+impl From<&str> for Uploaded {
+    fn from(input: &str) -> Self {
+        Self(input.to_string())
+    }
+}
+
+impl From<&str> for Edited {
+    fn from(input: &str) -> Self {
+        Self(input.to_string())
+    }
+}
+
 impl TryFrom<&str> for Name {
     type Error = Error;
 
@@ -486,10 +646,10 @@ impl TryFrom<&str> for Author {
         )
         .map_err(|why| Error::InvalidAuthor(StringError::FromUtf8(why)))?;
         if author.is_empty() {
-            return Err(Error::InvalidName(StringError::TooShort));
+            return Err(Error::InvalidAuthor(StringError::TooShort));
         }
         if author.len() > MAX_AUTHOR_LEN {
-            return Err(Error::InvalidName(StringError::TooLong {
+            return Err(Error::InvalidAuthor(StringError::TooLong {
                 max: MAX_AUTHOR_LEN as u64,
                 found: author.len() as u64,
             }));
@@ -564,8 +724,68 @@ impl FromStr for Key {
     }
 }
 
+impl std::fmt::Display for KeyHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+impl FromStr for KeyHash {
+    type Err = Error;
+
+    fn from_str(input: &str) -> std::prelude::v1::Result<Self, Self::Err> {
+        // `from_str_radix` also accepts a leading sign
+        if input.len() != 64 || !input.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(Error::InvalidKeyHash);
+        }
+        let mut bytes = [0; 32];
+        for (byte, hex) in bytes.iter_mut().zip(input.as_bytes().chunks(2)) {
+            let hex = std::str::from_utf8(hex).map_err(|_| Error::InvalidKeyHash)?;
+            *byte = u8::from_str_radix(hex, 16).map_err(|_| Error::InvalidKeyHash)?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl FromStr for PublicId {
+    type Err = Error;
+
+    fn from_str(input: &str) -> std::prelude::v1::Result<Self, Self::Err> {
+        // `from_str_radix` also accepts a leading sign
+        if input.len() != 16 || !input.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(Error::InvalidId);
+        }
+        u64::from_str_radix(input, 16)
+            .map(Self)
+            .map_err(|_| Error::InvalidId)
+    }
+}
+
 impl std::fmt::Display for Parsed {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Version: {}\nName: {}\nDescription: {}\nMusic: {}\nAuthor: {}\nBrand: {}\nBurdens: {}\nTiles: {}\nObjects: {}\nUploaded: {}\nEdited: {}", self.version, self.name, self.description, self.music, self.author, self.brand, self.burdens, self.tiles, self.objects, self.uploaded, self.edited)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_ids() {
+        let id = PublicId::from_str("6ae686efc996e6c5").expect("valid id");
+        assert_eq!(id.to_string(), "6ae686efc996e6c5");
+        let hash = "ab".repeat(32);
+        let key_hash = KeyHash::from_str(&hash).expect("valid key hash");
+        assert_eq!(key_hash.to_string(), hash);
+    }
+
+    #[test]
+    fn rejects_signed_ids() {
+        assert!(PublicId::from_str("+ae686efc996e6c5").is_err());
+        assert!(PublicId::from_str("-ae686efc996e6c5").is_err());
+        assert!(PublicId::from_str("6ae686efc996e6c").is_err());
+        assert!(KeyHash::from_str(&format!("+b{}", "ab".repeat(31))).is_err());
+        assert!(KeyHash::from_str(&format!("{}+b", "ab".repeat(31))).is_err());
+    }
+}
@@ -0,0 +1,33 @@
+//! Contains [`DataLock`], which keeps more than one
+//! Voyager from using the `voyager` directory at once.
+
+use crate::prelude::*;
+use std::fs::{create_dir, File, OpenOptions, TryLockError};
+
+/// An exclusive lock on `voyager/voyager.lock`, held by the server
+/// for as long as it runs, and by every other subcommand for as long
+/// as it takes. The lock is released when dropped (or on exit).
+///
+/// Subcommands that change the database while the server is running
+/// would be overwritten by the server's next save, so they refuse to.
+pub struct DataLock(#[allow(dead_code)] File);
+
+impl DataLock {
+    /// Locks the `voyager` directory, creating it if needed.
+    ///
+    /// # Errors
+    /// Returns [`Error::Locked`] if another Voyager holds the lock.
+    pub fn acquire() -> Result<Self> {
+        let _ = create_dir("voyager");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open("voyager/voyager.lock")?;
+        match file.try_lock() {
+            Ok(()) => Ok(Self(file)),
+            Err(TryLockError::WouldBlock) => Err(Error::Locked),
+            Err(TryLockError::Error(why)) => Err(why.into()),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Contains [`start_logging`], which sets up logging to stdout (or
+//! stderr) and to daily files in `voyager/logs`, and [`trace_request`], the middleware
+//! giving every request an ID and logging it once answered.
+//!
+//! See [`Logging`] for the settings.
+
+use crate::utils::config::{AccessLog, LogFormat, Logging};
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{header::CONTENT_LENGTH, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::{net::SocketAddr, time::Instant};
+use time::OffsetDateTime;
+use tracing::{info, info_span, Instrument, Subscriber};
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    filter::Targets, fmt, layer::SubscriberExt, registry::LookupSpan, util::SubscriberInitExt,
+    Layer,
+};
+use ulid::Ulid;
+
+/// The header a request's ID is read from and sent back in.
+pub const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The longest request ID a client may send. Longer (or
+/// otherwise unusable) IDs are replaced with a new one.
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Starts logging to stdout and to daily files in `voyager/logs`,
+/// deleting old log files if [`Logging::retention_days`] is set.
+///
+/// Only the server logs to stdout. Every other subcommand logs
+/// to stderr instead, so that its output (e.g. `levels list --json`)
+/// is all that ends up on stdout.
+///
+/// The file logger only periodically saves the logs to file. It
+/// also saves the logs to file when the returned guard is dropped.
+///
+/// # Panics
+/// Panics if a per-module log level is invalid, or if
+/// the log directory could not be created.
+pub fn start_logging(logging: &Logging, serving: bool) -> WorkerGuard {
+    let mut appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("voyager.log");
+    if logging.retention_days > 0 {
+        appender = appender.max_log_files(logging.retention_days);
+    }
+    let appender = appender
+        .build("voyager/logs")
+        .expect("log directory to be writable");
+    let (non_blocking, guard) = tracing_appender::non_blocking(appender);
+    let targets = |filter: &str| {
+        filter
+            .parse::<Targets>()
+            .expect("valid per-module log levels")
+    };
+    let console = if serving {
+        layer(logging.format, std::io::stdout, true)
+    } else {
+        layer(logging.format, std::io::stderr, true)
+    };
+    let stdout_log = console.with_filter(targets(&logging.stdout)).boxed();
+    let file_log = layer(logging.file_format, non_blocking, false)
+        .with_filter(targets(&logging.file))
+        .boxed();
+    tracing_subscriber::registry()
+        .with(stdout_log)
+        .with(file_log)
+        .init();
+    guard
+}
+
+/// Creates a logging layer in the given format.
+fn layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'writer> fmt::MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let layer = fmt::layer().with_writer(writer).with_ansi(ansi);
+    match format {
+        LogFormat::Pretty => layer.pretty().boxed(),
+        LogFormat::Full => layer.boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
+
+/// Gives every request an ID, taken from its `X-Request-ID` header
+/// if it has a usable one. The ID is attached to every log line
+/// about the request (as the `request_id` span field) and sent
+/// back in the response's `X-Request-ID` header.
+///
+/// Once answered, the request is logged according to
+/// [`Logging::access_log`], by its route (e.g. `/voyager/pending/:key`)
+/// rather than its path, so that keys never end up in the logs.
+pub async fn trace_request(
+    State(access_log): State<AccessLog>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let id = request
+        .headers()
+        .get(&REQUEST_ID)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .and_then(|id| id.to_str().ok())
+        .map_or_else(|| Ulid::new().to_string(), str::to_string);
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+    let version = request.version();
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map_or_else(
+            || "-".to_string(),
+            |ConnectInfo(addr)| addr.ip().to_string(),
+        );
+
+    let span = info_span!("request", request_id = %id);
+    let start = Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+    let latency = start.elapsed().as_secs_f64() * 1000.0;
+    let status = response.status().as_u16();
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID, value);
+    }
+
+    let _entered = span.enter();
+    match access_log {
+        AccessLog::Json => info!(
+            target: "voyager::access",
+            %method,
+            %route,
+            status,
+            latency_ms = latency,
+            %client,
+            "{method} {route} {status}"
+        ),
+        AccessLog::Common => {
+            let size = response
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|size| size.to_str().ok())
+                .unwrap_or("-");
+            info!(
+                target: "voyager::access",
+                "{client} - - [{}] \"{method} {route} {version:?}\" {status} {size} {latency:.3}ms",
+                common_log_time(OffsetDateTime::now_utc())
+            );
+        }
+        AccessLog::Off => {}
+    }
+    response
+}
+
+/// Formats a time as in Common Log Format, e.g. `18/Oct/2026:23:56:23 +0000`.
+fn common_log_time(time: OffsetDateTime) -> String {
+    let month = time.month().to_string();
+    format!(
+        "{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+        time.day(),
+        &month[..3],
+        time.year(),
+        time.hour(),
+        time.minute(),
+        time.second()
+    )
+}
@@ -0,0 +1,142 @@
+//! Contains [`Exporter`], which serves Voyager's metrics in
+//! Prometheus text format, and [`track`], the middleware
+//! counting and timing requests.
+//!
+//! The metrics are:
+//!
+//! - `voyager_requests_total` and `voyager_request_duration_seconds`,
+//!   per method, route, and status code.
+//! - `voyager_errors_total`, per [`Error::kind`].
+//! - `voyager_levels`, `voyager_pending`, `voyager_orphans`,
+//!   and `voyager_banned_ips`.
+//! - `voyager_save_duration_seconds` and `voyager_save_bytes`,
+//!   for saving the database.
+//! - `voyager_logins_total`, per result (`success` or `failure`).
+//!
+//! See [`Metrics`] for where they are served.
+
+use crate::prelude::*;
+// for documentation
+#[allow(unused_imports)]
+use crate::utils::config::Metrics;
+use ::metrics::{counter, histogram};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, Method,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use subtle::ConstantTimeEq;
+
+/// The histogram buckets (in seconds) for every `_seconds` metric.
+const DURATION_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// How often [`Exporter::upkeep`] drains the histograms.
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Serves the metrics, see the module documentation.
+pub struct Exporter {
+    db: SharedAppState,
+    handle: PrometheusHandle,
+    token: Option<String>,
+}
+
+impl Exporter {
+    /// Installs the global metrics recorder. Until then, metrics
+    /// are not recorded at all, e.g. when running a subcommand.
+    ///
+    /// # Errors
+    /// Returns [`Error::Metrics`] if a recorder was already installed.
+    pub fn install(db: SharedAppState) -> Result<Arc<Self>> {
+        let handle = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Suffix("seconds".to_string()), DURATION_BUCKETS)?
+            .install_recorder()?;
+        let token = db.config().metrics.token.clone();
+        Ok(Arc::new(Self { db, handle, token }))
+    }
+
+    /// Periodically drains the histograms, so that
+    /// they don't grow forever. Runs forever.
+    pub async fn upkeep(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(UPKEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            self.handle.run_upkeep();
+        }
+    }
+}
+
+/// Returns the metrics in Prometheus text format. If a token is
+/// configured (see [`Metrics::token`]), it must be sent as
+/// `Authorization: Bearer <token>`.
+///
+/// Returns 200 OK and the metrics. Returns 401
+/// UNAUTHORIZED on a missing or wrong token.
+pub async fn render(
+    State(exporter): State<Arc<Exporter>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse> {
+    if let Some(token) = &exporter.token {
+        let sent = headers
+            .get(AUTHORIZATION)
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix("Bearer "));
+        // compared in constant time, so the token can't be guessed by timing
+        let matches = sent.is_some_and(|sent| bool::from(sent.as_bytes().ct_eq(token.as_bytes())));
+        if !matches {
+            return Err(Error::Unauthorized);
+        }
+    }
+    exporter.db.record_counts();
+    Ok((
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        exporter.handle.render(),
+    ))
+}
+
+/// Counts and times every request, labeled by method (`other` for
+/// non-standard ones), route (e.g. `/voyager/stats/:id`), and status code.
+pub async fn track(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+    let method = method_label(request.method()).to_string();
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let labels = [
+        ("method", method),
+        ("route", route),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    counter!("voyager_requests_total", &labels).increment(1);
+    histogram!("voyager_request_duration_seconds", &labels).record(start.elapsed().as_secs_f64());
+    response
+}
+
+/// The method's label. Clients may send any method, and each label
+/// is a new time series, so only standard methods get their own.
+const fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::HEAD => "HEAD",
+        Method::OPTIONS => "OPTIONS",
+        Method::PATCH => "PATCH",
+        Method::TRACE => "TRACE",
+        Method::CONNECT => "CONNECT",
+        _ => "other",
+    }
+}
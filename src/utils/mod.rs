@@ -2,9 +2,35 @@
 //!
 //! Contains the Axum functions, the HTTP
 //! routers, and the level and parsed level
-//! structs along with their respective methods.
+//! structs along with their respective methods,
+//! as well as per-level statistics, replays, and
+//! revisions, Voyager's settings, and its
+//! command-line interface.
 
+pub mod admin;
+pub mod cli;
+pub mod config;
+pub mod creator;
+pub mod duplicate;
+pub mod filter;
+pub mod format;
+pub mod grid;
+pub mod health;
+pub mod index;
 pub mod level;
+pub mod lock;
+pub mod logging;
+pub mod metrics;
+pub mod pending;
+pub mod quota;
+pub mod ratelimit;
+pub mod replay;
+pub mod report;
+pub mod revision;
 pub mod routers;
 pub mod server;
+pub mod snapshot;
+pub mod stats;
+pub mod thumbnail;
+pub mod transfer;
 pub mod webui;
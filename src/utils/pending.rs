@@ -0,0 +1,37 @@
+//! Contains [`Pending`], a level held for review, and
+//! [`Decision`], a moderator's decision on one.
+
+use crate::prelude::*;
+use crate::utils::level::Validated;
+use serde::{Deserialize, Serialize};
+
+/// How many moderator [`Decision`]s are kept, the oldest
+/// ones are forgotten first.
+pub const MAX_DECISIONS: usize = 1000;
+
+/// A level (or an edit of one) held for review in the Web UI,
+/// instead of being made public. Approving it makes it public,
+/// rejecting it forgets it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pending {
+    /// The level, as it would be made public.
+    pub level: Level<Validated>,
+    /// Why the level was held, e.g. `word list`.
+    pub reason: String,
+}
+
+/// A moderator's decision on a [`Pending`] level, kept as a record
+/// of who approved or rejected what.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Decision {
+    /// The level's public ID.
+    pub id: PublicId,
+    /// The level's name, as it was when the decision was made.
+    pub name: String,
+    /// Whether the level was approved (or rejected).
+    pub approved: bool,
+    /// The Web UI username of the moderator.
+    pub moderator: String,
+    /// When the decision was made, in `yyyymmdd` format.
+    pub date: String,
+}
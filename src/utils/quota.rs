@@ -0,0 +1,60 @@
+//! Contains [`Quota`], how many levels an IP adress may upload,
+//! and [`DailyCount`], how many it has uploaded today.
+//!
+//! Unlike [`RateLimiter`], which limits short bursts of requests,
+//! quotas limit how many levels are adopted (see [`orphanage`]) per
+//! day, per IP adress and per author brand, and how many levels from
+//! one IP adress may exist at once. The default quotas are set in
+//! [`Quotas`], and may be overridden per IP adress from the Web UI.
+
+use crate::utils::level::today;
+use serde::{Deserialize, Serialize};
+
+// for documentation
+#[allow(unused_imports)]
+use crate::utils::{config::Quotas, ratelimit::RateLimiter, routers::post::orphanage};
+
+/// An overridden quota for a single (trusted) IP adress.
+///
+/// An IP adress with an overridden quota is not
+/// limited by the per-brand daily quota.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Quota {
+    /// How many levels may be adopted per day.
+    pub daily: u32,
+    /// How many levels may exist at once.
+    pub live: usize,
+}
+
+/// How many levels have been adopted on a given day.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DailyCount {
+    /// The day, in `yyyymmdd` format.
+    day: String,
+    /// How many levels were adopted on that day.
+    count: u32,
+}
+
+impl DailyCount {
+    /// How many levels have been adopted today.
+    #[must_use]
+    pub fn today(&self) -> u32 {
+        if self.day == today() {
+            self.count
+        } else {
+            0
+        }
+    }
+
+    /// Counts one more level adopted today.
+    pub fn increment(&mut self) {
+        let today = today();
+        if self.day != today {
+            *self = Self {
+                day: today,
+                count: 0,
+            };
+        }
+        self.count += 1;
+    }
+}
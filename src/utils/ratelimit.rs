@@ -0,0 +1,105 @@
+//! Contains [`RateLimiter`], a per-IP token bucket rate limiter,
+//! and [`rate_limit`], the middleware using it.
+//!
+//! Every IP adress gets a bucket holding up to [`Limit::burst`]
+//! tokens, refilled at [`Limit::per_minute`] tokens per minute.
+//! Every request takes one token, and requests made with an
+//! empty bucket are refused with [`Error::RateLimited`].
+
+use crate::prelude::*;
+use crate::utils::config::Limit;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// A bucket of tokens belonging to one IP adress.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    /// How many tokens are left, as of `updated`.
+    tokens: f64,
+    /// When the bucket was last refilled.
+    updated: Instant,
+}
+
+/// A token bucket rate limiter, keyed on IP adress.
+///
+/// See the module documentation for details.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: Limit,
+    buckets: DashMap<IpAddr, Bucket>,
+}
+
+impl RateLimiter {
+    /// Creates a new rate limiter with no buckets.
+    #[must_use]
+    pub fn new(limit: Limit) -> Arc<Self> {
+        Arc::new(Self {
+            limit,
+            buckets: DashMap::new(),
+        })
+    }
+
+    /// Takes a token from the IP adress' bucket, if there is one.
+    ///
+    /// # Errors
+    /// Returns [`Error::RateLimited`] if the bucket is empty.
+    pub fn check(&self, input: IpAddr) -> Result<()> {
+        let now = Instant::now();
+        let burst = f64::from(self.limit.burst.get());
+        let mut bucket = self.buckets.entry(input).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = elapsed.mul_add(self.per_second(), bucket.tokens).min(burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        let missing = 1.0 - bucket.tokens;
+        drop(bucket);
+        // at most a minute, as at least one token is added per minute
+        let retry_after = Duration::from_secs_f64(missing / self.per_second()).as_secs() + 1;
+        Err(Error::RateLimited { retry_after })
+    }
+
+    /// Forgets every bucket that has been idle long enough to
+    /// be full again, as those behave the same as a new bucket.
+    pub fn evict_idle(&self) {
+        let burst = f64::from(self.limit.burst.get());
+        let per_second = self.per_second();
+        let now = Instant::now();
+        self.buckets.retain(|_, bucket| {
+            let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+            elapsed.mul_add(per_second, bucket.tokens) < burst
+        });
+    }
+
+    fn per_second(&self) -> f64 {
+        f64::from(self.limit.per_minute.get()) / 60.0
+    }
+}
+
+/// Middleware refusing requests from IP adresses which
+/// have run out of tokens in the given [`RateLimiter`].
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match limiter.check(addr.ip()) {
+        Ok(()) => next.run(request).await,
+        Err(why) => why.into_response(),
+    }
+}
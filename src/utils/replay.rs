@@ -0,0 +1,327 @@
+//! Server-side verification of replays (move sequences) against
+//! a level, and the per-level step-count [`Record`]s they earn.
+//!
+//! The simulation is a deterministic model of Void Stranger's core
+//! rules: walking, falling into pits, and picking up or placing floor
+//! tiles with the void rod. Anything else standing on the grid (statues,
+//! enemies, chests, and so on) is treated as an immovable obstacle.
+//!
+//! The level is played on its tiles. Objects may have more rows than
+//! the tiles (e.g. 14×9 objects on 14×8 tiles); objects below the last
+//! row of tiles are off the playing field, and are ignored.
+
+use crate::prelude::*;
+use crate::utils::{
+    grid::{Cell, Grid},
+    level::Brand,
+};
+// for documentation
+#[allow(unused_imports)]
+use crate::utils::level::{Objects, Tiles};
+use serde::{Deserialize, Serialize};
+
+/// A replay's max amount of moves.
+pub const MAX_REPLAY_LEN: usize = 10_000;
+
+/// How many [`Record`]s a level's leaderboard keeps, the
+/// highest step counts are forgotten first.
+pub const MAX_LEADERBOARD_LEN: usize = 100;
+
+/// A verified step count on a level's leaderboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    /// The author brand of the player who set the record.
+    pub brand: Brand,
+    /// How many steps the replay took.
+    pub steps: u32,
+    /// When the record was set, in `yyyymmdd` format (UTC).
+    pub date: String,
+}
+
+/// A single move in a replay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Move {
+    /// `U`: Walk or turn up.
+    Up,
+    /// `D`: Walk or turn down.
+    Down,
+    /// `L`: Walk or turn left.
+    Left,
+    /// `R`: Walk or turn right.
+    Right,
+    /// `A`: Use the void rod on the tile in front of the player.
+    Rod,
+}
+
+impl TryFrom<char> for Move {
+    type Error = ReplayError;
+
+    fn try_from(input: char) -> std::result::Result<Self, Self::Error> {
+        match input {
+            'U' => Ok(Self::Up),
+            'D' => Ok(Self::Down),
+            'L' => Ok(Self::Left),
+            'R' => Ok(Self::Right),
+            'A' => Ok(Self::Rod),
+            other => Err(ReplayError::InvalidMove(other)),
+        }
+    }
+}
+
+/// The player's state while a replay is being simulated.
+struct Simulation {
+    tiles: Grid,
+    objects: Grid,
+    x: usize,
+    y: usize,
+    facing: Move,
+    rod: Option<Cell>,
+}
+
+impl Simulation {
+    fn new(tiles: Grid, objects: Grid) -> std::result::Result<Self, ReplayError> {
+        let players = objects
+            .cells()
+            .filter(|(_, y, cell)| cell.id() == "pl" && *y < tiles.rows())
+            .map(|(x, y, _)| (x, y))
+            .collect::<Vec<(usize, usize)>>();
+        let [(x, y)] = players[..] else {
+            return Err(ReplayError::NoPlayer);
+        };
+        Ok(Self {
+            tiles,
+            objects,
+            x,
+            y,
+            facing: Move::Down,
+            rod: None,
+        })
+    }
+
+    /// The position in front of the player, if it is inside the grid.
+    fn front(&self) -> Option<(usize, usize)> {
+        let (x, y) = match self.facing {
+            Move::Up => (Some(self.x), self.y.checked_sub(1)),
+            Move::Down => (Some(self.x), Some(self.y + 1)),
+            Move::Left => (self.x.checked_sub(1), Some(self.y)),
+            // the player never faces the rod
+            Move::Right | Move::Rod => (Some(self.x + 1), Some(self.y)),
+        };
+        let (x, y) = (x?, y?);
+        self.tiles.get(x, y).map(|_| (x, y))
+    }
+
+    /// Whether nothing but the player stands at the position.
+    fn is_free(&self, x: usize, y: usize) -> bool {
+        self.objects
+            .get(x, y)
+            .is_none_or(|object| matches!(object.id(), "em" | "pl"))
+    }
+
+    /// Plays a single move, returning whether it took a step.
+    fn play(&mut self, input: Move) -> bool {
+        if input == Move::Rod {
+            return self.use_rod();
+        }
+        self.facing = input;
+        let Some((x, y)) = self.front() else {
+            return false;
+        };
+        let tile = self.tiles.get(x, y).map(Cell::id);
+        if tile == Some("wa") || !self.is_free(x, y) {
+            return false;
+        }
+        (self.x, self.y) = (x, y);
+        true
+    }
+
+    /// Picks up the floor tile in front of the player, or places the held
+    /// one into the pit in front of the player. Returns whether it did either.
+    fn use_rod(&mut self) -> bool {
+        let Some((x, y)) = self.front() else {
+            return false;
+        };
+        let Some(tile) = self.tiles.get(x, y).cloned() else {
+            return false;
+        };
+        match (self.rod.take(), tile.id()) {
+            (None, "pt" | "wa" | "ex") => false,
+            (None, _) if self.is_free(x, y) => {
+                self.tiles.set(x, y, Cell::new("pt"));
+                self.rod = Some(tile);
+                true
+            }
+            (Some(held), "pt") => {
+                self.tiles.set(x, y, held);
+                true
+            }
+            (held, _) => {
+                self.rod = held;
+                false
+            }
+        }
+    }
+
+    /// The id of the tile the player is standing on.
+    ///
+    /// The player starts on the tiles and only ever steps
+    /// [in front](Self::front) of itself, so it is never off them.
+    fn standing_on(&self) -> &str {
+        self.tiles.get(self.x, self.y).map_or("pt", Cell::id)
+    }
+}
+
+/// Replays a move sequence against a level's [`Tiles`] and [`Objects`].
+///
+/// Moves are given as a string of `U`, `D`, `L`, `R` (walk or turn)
+/// and `A` (use the void rod). Walking into a wall or an obstacle only
+/// turns the player and does not count as a step.
+///
+/// Returns how many steps it took to reach the exit.
+///
+/// # Errors
+/// Returns an [`Error::InvalidReplay`] if the level could not be decoded,
+/// the replay has an invalid or too many moves, the player falls into a
+/// pit, or the replay ends anywhere but on the exit.
+pub fn verify(level: &Parsed, moves: &str) -> Result<u32> {
+    let found = moves.chars().count();
+    if found > MAX_REPLAY_LEN {
+        return Err(Error::InvalidReplay(ReplayError::TooLong {
+            max: MAX_REPLAY_LEN as u64,
+            found: found as u64,
+        }));
+    }
+    let moves = moves
+        .chars()
+        .map(Move::try_from)
+        .collect::<std::result::Result<Vec<Move>, ReplayError>>()
+        .map_err(Error::InvalidReplay)?;
+    let (Some(tiles), Some(objects)) = (level.tiles.decode(), level.objects.decode()) else {
+        return Err(Error::InvalidReplay(ReplayError::UndecodableLevel));
+    };
+    simulate(tiles, objects, moves).map_err(Error::InvalidReplay)
+}
+
+/// Replays moves against decoded tiles and objects, see [`verify`].
+fn simulate(tiles: Grid, objects: Grid, moves: Vec<Move>) -> std::result::Result<u32, ReplayError> {
+    let mut simulation = Simulation::new(tiles, objects)?;
+
+    let mut steps = 0;
+    let mut moves = moves.into_iter();
+    while let Some(input) = moves.next() {
+        if simulation.play(input) {
+            steps += 1;
+        }
+        match simulation.standing_on() {
+            "pt" => return Err(ReplayError::Fell { step: steps }),
+            "ex" if moves.len() > 0 => return Err(ReplayError::MovesAfterExit { step: steps }),
+            "ex" => return Ok(steps),
+            _ => (),
+        }
+    }
+    Err(ReplayError::ExitNotReached)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::grid::GRID_WIDTH;
+
+    /// Decodes rows of space-separated cells, padding every row
+    /// to [`GRID_WIDTH`] cells with `padding`.
+    fn grid(rows: &[&str], padding: &str) -> Grid {
+        let cells = rows
+            .iter()
+            .flat_map(|row| {
+                let mut cells = row.split_whitespace().collect::<Vec<&str>>();
+                cells.resize(GRID_WIDTH, padding);
+                cells
+            })
+            .collect::<String>();
+        Grid::decode(&cells).expect("valid test grid")
+    }
+
+    fn run(tiles: &[&str], objects: &[&str], moves: &str) -> std::result::Result<u32, ReplayError> {
+        let moves = moves
+            .chars()
+            .map(Move::try_from)
+            .collect::<std::result::Result<Vec<Move>, ReplayError>>()?;
+        simulate(grid(tiles, "wa"), grid(objects, "em"), moves)
+    }
+
+    #[test]
+    fn reaches_the_exit() {
+        assert_eq!(run(&["fl fl ex"], &["pl"], "RR"), Ok(2));
+    }
+
+    #[test]
+    fn walls_only_turn_the_player() {
+        let tiles = ["fl fl ex", "wa wa wa"];
+        assert_eq!(run(&tiles, &["pl"], "DRR"), Ok(2));
+        assert_eq!(
+            run(&["fl wa ex"], &["pl"], "RR"),
+            Err(ReplayError::ExitNotReached)
+        );
+    }
+
+    #[test]
+    fn obstacles_only_turn_the_player() {
+        assert_eq!(
+            run(&["fl fl ex"], &["pl st"], "RR"),
+            Err(ReplayError::ExitNotReached)
+        );
+    }
+
+    #[test]
+    fn falls_into_pits() {
+        assert_eq!(
+            run(&["fl pt ex"], &["pl"], "RR"),
+            Err(ReplayError::Fell { step: 1 })
+        );
+    }
+
+    #[test]
+    fn rod_picks_up_and_places_floor() {
+        let tiles = ["wa fl fl pt ex", "wa fl wa wa wa"];
+        let objects = ["em pl"];
+        assert_eq!(
+            run(&tiles, &objects, "RR"),
+            Err(ReplayError::Fell { step: 2 })
+        );
+        // pick up the floor below, then fill the pit with it
+        assert_eq!(run(&tiles, &objects, "ARARR"), Ok(5));
+        // nothing to place without picking up first
+        assert_eq!(
+            run(&tiles, &objects, "RARR"),
+            Err(ReplayError::Fell { step: 2 })
+        );
+    }
+
+    #[test]
+    fn rod_only_picks_up_floor() {
+        // neither a step nor a pickup
+        assert_eq!(run(&["fl fl ex", "wa wa wa"], &["pl"], "ARR"), Ok(2));
+        assert_eq!(
+            run(&["fl fl pt ex"], &["pl"], "RAR"),
+            Err(ReplayError::Fell { step: 2 })
+        );
+        assert_eq!(run(&["fl fl ex"], &["pl"], "RAR"), Ok(2));
+    }
+
+    #[test]
+    fn stops_at_the_exit() {
+        assert_eq!(
+            run(&["fl ex fl"], &["pl"], "RR"),
+            Err(ReplayError::MovesAfterExit { step: 1 })
+        );
+    }
+
+    #[test]
+    fn ignores_objects_below_the_tiles() {
+        let tiles = ["fl fl ex"];
+        assert_eq!(run(&tiles, &["pl", "st"], "RR"), Ok(2));
+        assert_eq!(run(&tiles, &["em", "pl"], "RR"), Err(ReplayError::NoPlayer));
+        // the row below the tiles cannot be walked onto
+        assert_eq!(run(&tiles, &["pl", "em"], "DRR"), Ok(2));
+    }
+}
@@ -0,0 +1,59 @@
+//! Contains [`Report`], a player's report of a level,
+//! and [`Reason`], why it was reported.
+
+use crate::prelude::*;
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// The maximum length of a report's comment, in bytes.
+pub const MAX_COMMENT_LEN: usize = 500;
+
+/// Why a level was reported.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Reason {
+    /// The level's name, description, or author is offensive.
+    #[display(fmt = "offensive")]
+    Offensive,
+    /// The level is spam, e.g. empty or uploaded over and over.
+    #[display(fmt = "spam")]
+    Spam,
+    /// The level cannot be cleared, or does not load.
+    #[display(fmt = "broken")]
+    Broken,
+    /// The level was made by someone else.
+    #[display(fmt = "stolen")]
+    Stolen,
+    /// Anything else, hopefully explained in the comment.
+    #[display(fmt = "other")]
+    Other,
+}
+
+/// A player's report of a level.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    /// The reporting player's IP adress. Each IP adress
+    /// may only report each level once.
+    pub reporter: IpAddr,
+    /// Why the level was reported.
+    pub reason: Reason,
+    /// An optional comment, up to [`MAX_COMMENT_LEN`] long.
+    pub comment: String,
+    /// When the level was reported, in `yyyymmdd` format.
+    pub date: String,
+}
+
+impl TryFrom<&str> for Reason {
+    type Error = Error;
+
+    fn try_from(input: &str) -> std::prelude::v1::Result<Self, Self::Error> {
+        match input {
+            "offensive" => Ok(Self::Offensive),
+            "spam" => Ok(Self::Spam),
+            "broken" => Ok(Self::Broken),
+            "stolen" => Ok(Self::Stolen),
+            "other" => Ok(Self::Other),
+            _ => Err(Error::InvalidReason),
+        }
+    }
+}
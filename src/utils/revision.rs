@@ -0,0 +1,107 @@
+//! Contains [`Revision`], a previous version of an edited
+//! level, and [`Diff`], the changes between two versions.
+
+use crate::prelude::*;
+use crate::utils::{grid::Grid, level::Validated};
+use serde::{Deserialize, Serialize};
+
+/// A version of a level, kept when the level is edited.
+///
+/// The level's data includes its edited date, and the
+/// level's uploader is whoever uploaded this version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    /// The revision's number. The first upload of a level
+    /// is revision 0, and every edit counts up by one.
+    pub number: u32,
+    /// The level as it was.
+    pub level: Level<Validated>,
+}
+
+/// The changes between two versions of a level.
+#[derive(Debug, Default, Serialize)]
+pub struct Diff {
+    /// Every changed field, other than decodable tiles and objects.
+    pub fields: Vec<FieldChange>,
+    /// Every changed tile and object.
+    pub cells: Vec<CellChange>,
+}
+
+/// A changed field, such as the name.
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    /// The field's name.
+    pub field: &'static str,
+    /// The field's old value.
+    pub from: String,
+    /// The field's new value.
+    pub to: String,
+}
+
+/// A changed tile or object.
+#[derive(Debug, Serialize)]
+pub struct CellChange {
+    /// Either `tiles` or `objects`.
+    pub layer: &'static str,
+    /// The cell's column.
+    pub x: usize,
+    /// The cell's row.
+    pub y: usize,
+    /// The old cell, e.g. `fl`.
+    pub from: String,
+    /// The new cell, e.g. `pt`.
+    pub to: String,
+}
+
+impl Diff {
+    /// Compares two versions of a level, field by field
+    /// and tile by tile.
+    ///
+    /// Tiles and objects are compared cell by cell if both versions
+    /// can be decoded into equally big grids, or as a whole otherwise.
+    #[must_use]
+    pub fn new(from: &Parsed, to: &Parsed) -> Self {
+        let mut diff = Self::default();
+        diff.field("version", &from.version, &to.version);
+        diff.field("name", &from.name, &to.name);
+        diff.field("description", &from.description, &to.description);
+        diff.field("music", &from.music, &to.music);
+        diff.field("author", &from.author, &to.author);
+        diff.field("brand", &from.brand, &to.brand);
+        diff.field("burdens", &from.burdens, &to.burdens);
+        match (from.tiles.decode(), to.tiles.decode()) {
+            (Some(old), Some(new)) if old.cells().count() == new.cells().count() => {
+                diff.grid("tiles", &old, &new);
+            }
+            _ => diff.field("tiles", &from.tiles, &to.tiles),
+        }
+        match (from.objects.decode(), to.objects.decode()) {
+            (Some(old), Some(new)) if old.cells().count() == new.cells().count() => {
+                diff.grid("objects", &old, &new);
+            }
+            _ => diff.field("objects", &from.objects, &to.objects),
+        }
+        diff
+    }
+
+    fn field(&mut self, field: &'static str, from: &impl ToString, to: &impl ToString) {
+        let (from, to) = (from.to_string(), to.to_string());
+        if from != to {
+            self.fields.push(FieldChange { field, from, to });
+        }
+    }
+
+    fn grid(&mut self, layer: &'static str, from: &Grid, to: &Grid) {
+        for ((x, y, old), (_, _, new)) in from.cells().zip(to.cells()) {
+            if old != new {
+                self.cells.push(CellChange {
+                    layer,
+                    x,
+                    y,
+                    from: old.to_string(),
+                    to: new.to_string(),
+                });
+            }
+        }
+    }
+}
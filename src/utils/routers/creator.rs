@@ -0,0 +1,154 @@
+//! Routers for creator accounts, see [`Creator`].
+//!
+//! Every router but [`register`] takes in a creator's credentials,
+//! see [`credentials`].
+
+use crate::prelude::*;
+use crate::utils::{
+    creator::{self, blocking, Creator},
+    level::{Author, Brand},
+};
+// for documentation
+#[allow(unused_imports)]
+use crate::utils::creator::credentials;
+use axum::{
+    extract::{ConnectInfo, Path, State},
+    http::{HeaderMap, StatusCode},
+};
+use itertools::Itertools;
+use std::net::SocketAddr;
+use tracing::info;
+
+/// Returns the creator whose credentials were sent.
+async fn authenticate(db: &SharedAppState, headers: &HeaderMap) -> Result<Creator> {
+    creator::authenticate(db, headers)
+        .await?
+        .ok_or(Error::Unauthorized)
+}
+
+/// Registers a creator account for an author brand and name.
+///
+/// The format is as follows:
+///
+/// `brand|author|key|secret`
+///
+/// Where `author` is encoded as in a level (see
+/// [`Data`](crate::utils::level::Data)), `key` is the key of
+/// a level with the brand, proving that the brand is the
+/// creator's, and `secret` is what the creator will
+/// authenticate with. The level then belongs to the creator.
+///
+/// Returns 201 CREATED if successful. Returns 400 BAD REQUEST on invalid
+/// input. Returns 403 FORBIDDEN if the level does not have the brand.
+/// Returns 404 NOT FOUND on valid key, but no matching level. Returns
+/// 409 CONFLICT if the brand already has a creator account.
+pub async fn register(
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    input: String,
+) -> Result<StatusCode> {
+    let addr = addr.ip();
+    info!("REGISTER sent by {addr}");
+    if db.ip_is_banned(&addr) {
+        return Err(Error::Banned);
+    }
+    let (brand, author, key, secret) = input
+        .splitn(4, '|')
+        .collect_tuple()
+        .ok_or(Error::InvalidStructure)?;
+    let brand = Brand::try_from(brand)?;
+    let author = Author::try_from(author)?;
+    let key = key.parse()?;
+    let secret = secret.to_string();
+    let creator = blocking(move || Creator::new(brand, author, &secret)).await?;
+    db.register(creator, &key)?;
+    Ok(StatusCode::CREATED)
+}
+
+/// Returns a comma-separated list of every level belonging to the
+/// creator, including levels held for review, each followed by its
+/// public ID. See [`Data`](crate::utils::level::Data).
+///
+/// Returns 200 OK and the list. Returns 401 UNAUTHORIZED
+/// on invalid credentials.
+pub async fn levels(
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<String> {
+    info!("GET creator levels sent by {}", addr.ip());
+    let creator = authenticate(&db, &headers).await?;
+    Ok(db.creator_levels(creator.brand).join(","))
+}
+
+/// Makes a level uploaded without signing it belong to the
+/// creator. Takes in the level's key, and returns its public ID.
+///
+/// Returns 200 OK and the public ID. Returns 400 BAD REQUEST on invalid
+/// key. Returns 401 UNAUTHORIZED on invalid credentials. Returns 403
+/// FORBIDDEN if the level does not have the creator's brand. Returns
+/// 404 NOT FOUND on valid key, but no matching level.
+pub async fn claim(
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    key: String,
+) -> Result<String> {
+    info!("CLAIM sent by {}", addr.ip());
+    let creator = authenticate(&db, &headers).await?;
+    let id = db.claim(creator.brand, &key.parse()?)?;
+    Ok(id.to_string())
+}
+
+/// Gives one of the creator's levels a new key, e.g.
+/// `/voyager/creator/recover/:id`, and returns it. The old key
+/// stops working.
+///
+/// Returns 200 OK and a ULID key. Returns 400 BAD REQUEST on invalid
+/// ID. Returns 401 UNAUTHORIZED on invalid credentials. Returns 403
+/// FORBIDDEN if the level does not belong to the creator. Returns
+/// 404 NOT FOUND on valid ID, but no matching level.
+pub async fn recover(
+    Path(id): Path<String>,
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<String> {
+    info!("RECOVER sent by {} for {id}", addr.ip());
+    let creator = authenticate(&db, &headers).await?;
+    let key = db.recover(creator.brand, id.parse()?)?;
+    Ok(key.to_string())
+}
+
+/// Renames the creator across every level belonging to them. Takes
+/// in the new author, encoded as in a level, and returns how many
+/// levels were renamed.
+///
+/// Returns 200 OK and the count. Returns 400 BAD REQUEST on an invalid
+/// author (or a blocked word). Returns 401 UNAUTHORIZED on invalid credentials.
+pub async fn rename(
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    author: String,
+) -> Result<String> {
+    info!("RENAME sent by {}", addr.ip());
+    let creator = authenticate(&db, &headers).await?;
+    let author = Author::try_from(author.trim())?;
+    Ok(db.rename(creator.brand, &author)?.to_string())
+}
+
+/// Deletes every level belonging to the creator, and
+/// returns how many levels were deleted.
+///
+/// Returns 200 OK and the count. Returns 401 UNAUTHORIZED
+/// on invalid credentials.
+pub async fn delete(
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<String> {
+    info!("DELETE creator levels sent by {}", addr.ip());
+    let creator = authenticate(&db, &headers).await?;
+    Ok(db.delete_creator_levels(creator.brand).to_string())
+}
@@ -21,12 +21,12 @@ pub async fn delete(
     key: String,
 ) -> Result<StatusCode> {
     let addr = addr.ip();
-    info!("DELETE sent by {addr} for {key}");
+    info!("DELETE sent by {addr}");
     if db.ip_is_banned(&addr) {
         info!("{addr} is banned");
         return Err(Error::Banned);
     }
-    let key = key.parse()?;
-    info!("Deleting level {key}...");
-    db.delete(&key)
+    let id = db.find_key(&key.parse()?)?;
+    info!("Deleting level {id}...");
+    db.delete(id)
 }
@@ -1,13 +1,21 @@
 //! Routers for the GET HTTP method.
 
 use crate::prelude::*;
+use crate::utils::{index::Filter, level::Version};
 //for documentation
 #[allow(unused_imports)]
-use crate::utils::level::Data;
+use crate::utils::{format::Format, level::Data};
 use axum::{
-    extract::{ConnectInfo, Path, State},
-    http::StatusCode,
+    extract::{ConnectInfo, Path, Query, State},
+    http::{
+        header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
 };
+use serde::Deserialize;
+use serde_json::{json, Value};
 use std::net::SocketAddr;
 use tracing::info;
 
@@ -15,13 +23,84 @@ use tracing::info;
 ///
 /// See [`Data`] for details on level format.
 ///
-/// Returns 200 OK and a comma-separated list.
+/// The response has `ETag` and `Last-Modified` headers taken from the
+/// database's generation. If the request's `If-None-Match` (or, if not
+/// given, `If-Modified-Since`) header shows that the client already has
+/// the current list, Voyager instead returns 304 NOT MODIFIED and no list.
+///
+/// The list may be narrowed down with `brand`, `author`, and `uploaded`
+/// queries, e.g. `/voyager?author=hexfae&uploaded=20240131`. Only levels
+/// matching every given query are returned.
+///
+/// Levels are listed in the format version they were uploaded in, unless
+/// a `version` query asks for another one (see [`Format`]), e.g.
+/// `/voyager?version=1`. Levels uploaded in another version are then
+/// converted, or left out if they cannot be, and every level is followed
+/// by its public ID. Without a `version` query, the list is exactly what
+/// older clients expect, so it deliberately leaves public IDs out: those
+/// clients split every field of the listing and would choke on an extra one.
+///
+/// Returns 200 OK and a comma-separated list, 304 NOT MODIFIED, or
+/// 400 BAD REQUEST on an invalid query or unsupported version.
 pub async fn get(
+    Query(mut filter): Query<Filter>,
+    Query(listing): Query<Listing>,
     State(db): State<SharedAppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-) -> (StatusCode, String) {
+    headers: HeaderMap,
+) -> Result<Response> {
     info!("GET sent by {}", addr.ip());
-    (StatusCode::OK, db.levels())
+    // uploaders are private, see the web ui instead
+    filter.uploader = None;
+    let format = listing
+        .version
+        .as_deref()
+        .map(|version| Version::try_from(version).map(|version| version.format()))
+        .transpose()?;
+    let (generation, modified) = db.generation();
+    // listings in different versions must not share an etag
+    let version = format.map_or_else(String::new, |format| format!("-v{}", format.version()));
+    let etag = format!("W/\"{generation}{version}\"");
+    let modified = httpdate::fmt_http_date(modified.into());
+    let cache_headers = [(ETAG, etag.clone()), (LAST_MODIFIED, modified.clone())];
+
+    if is_not_modified(&headers, &etag, &modified) {
+        info!("GET not modified for {}", addr.ip());
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+    let levels = match format {
+        Some(format) => db.levels_matching_in(&filter, format)?,
+        None => db.levels_matching(&filter)?,
+    };
+    Ok((StatusCode::OK, cache_headers, levels).into_response())
+}
+
+/// The format version to list levels in, see [`get`].
+#[derive(Debug, Default, Deserialize)]
+pub struct Listing {
+    /// The format version, e.g. `1`.
+    pub version: Option<String>,
+}
+
+/// Whether the request's conditional headers match the current
+/// `ETag` or `Last-Modified`. `If-None-Match` takes precedence.
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified: &str) -> bool {
+    if let Some(if_none_match) = headers.get(IF_NONE_MATCH) {
+        let Ok(if_none_match) = if_none_match.to_str() else {
+            return false;
+        };
+        // weak comparison, as responses may be compressed
+        let weak = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+        return if_none_match
+            .split(',')
+            .any(|tag| tag.trim() == "*" || weak(tag) == weak(etag));
+    }
+    let since = headers
+        .get(IF_MODIFIED_SINCE)
+        .and_then(|since| since.to_str().ok())
+        .and_then(|since| httpdate::parse_http_date(since).ok());
+    let modified = httpdate::parse_http_date(modified).ok();
+    matches!((since, modified), (Some(since), Some(modified)) if modified <= since)
 }
 
 // TODO: candidate for refactoring
@@ -36,7 +115,9 @@ pub async fn get(
 /// are valid, but `key3` is not, Voyager will return 200 OK and `1101`.
 /// If any key fails to parse, Voyager will instead return 400 BAD REQUEST.
 ///
-/// Valid is defined as "a level with that key exists in the database."
+/// Valid is defined as "a level with that key exists in the database,"
+/// including levels held for review. Only the keys' hashes are stored,
+/// see [`Key::hash`].
 ///
 /// Returns 200 OK and a sequence of 0's and 1's, or 400 BAD REQUEST.
 pub async fn levels_exist(
@@ -58,7 +139,7 @@ pub async fn levels_exist(
     }
     let found = keys
         .iter()
-        .map(|key| i32::from(db.contains(key)).to_string())
+        .map(|key| i32::from(db.find_key(key).is_ok()).to_string())
         .collect::<Vec<String>>();
     let existing = found.join("");
     info!(
@@ -67,3 +148,70 @@ pub async fn levels_exist(
     );
     Ok((StatusCode::OK, existing))
 }
+
+/// Returns a level held for review, so that its uploader may
+/// see it before it is public.
+///
+/// Takes in a [ULID](https://github.com/ulid/spec) key, e.g.
+/// `/voyager/pending/:key`. See [`Data`] for details on level format.
+///
+/// Returns 200 OK and the level. Returns 400 BAD REQUEST on invalid
+/// key. Returns 404 NOT FOUND if no level with that key is held.
+pub async fn pending(
+    Path(key): Path<String>,
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<String> {
+    info!("GET pending sent by {}", addr.ip());
+    let level = db.get_pending(db.find_key(&key.parse()?)?)?;
+    Ok(level.data.to_string())
+}
+
+/// The query of a [`changes`] request.
+#[derive(Deserialize)]
+pub struct Since {
+    /// The cursor returned by the previous request,
+    /// or 0 (the default) to get every level.
+    #[serde(default)]
+    since: u64,
+}
+
+/// Returns every level added or edited, and every level deleted,
+/// since a cursor, so that Endless Void may keep a local cache of
+/// the levels instead of downloading all of them with [`get`].
+///
+/// Takes in a `since` query, e.g. `/voyager/changes?since=42`. Levels
+/// are identified by their [`PublicId`], and deleted levels are given as
+/// a list of those IDs (tombstones). For example:
+///
+/// `{"cursor":44,"resync":false,"levels":[{"id":"3f1c0a9b2e7d4c51","data":"1|Zm9v|..."}],"deleted":["9a0be14c77d2f3e8"]}`
+///
+/// The returned `cursor` should be used as `since` in the next request.
+///
+/// Deleted levels are only remembered for a while (see
+/// [`Config::tombstone_ttl`](crate::utils::config::Config::tombstone_ttl)).
+/// If `since` is older than that, `resync` is `true` and `levels` holds
+/// every level, and the client should forget every level it has.
+///
+/// See [`Data`] for details on level format.
+///
+/// Returns 200 OK and the changes, or 400 BAD REQUEST on an invalid cursor.
+pub async fn changes(
+    Query(Since { since }): Query<Since>,
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Json<Value> {
+    info!("GET changes sent by {} since {since}", addr.ip());
+    let changes = db.changes(since);
+    let levels = changes
+        .levels
+        .into_iter()
+        .map(|level| json!({ "id": level.id.to_string(), "data": level.data.to_string() }))
+        .collect::<Vec<Value>>();
+    Json(json!({
+        "cursor": changes.cursor,
+        "resync": changes.resync,
+        "levels": levels,
+        "deleted": changes.deleted.iter().map(ToString::to_string).collect::<Vec<String>>(),
+    }))
+}
@@ -0,0 +1,46 @@
+//! Routers for liveness and readiness checks, for
+//! process supervisors and load balancers.
+
+use crate::prelude::*;
+use axum::{extract::State, http::StatusCode, Json};
+use serde_json::{json, Value};
+use tracing::warn;
+
+/// Answers as long as the server is running. Cheap
+/// enough to be probed as often as needed.
+///
+/// Returns 200 OK and `{"status": "ok"}`.
+pub async fn healthz() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Checks whether the server is ready for requests: the database
+/// was loaded, the `voyager` directory is writable (checked at most
+/// every few seconds), the latest save succeeded, and the server is
+/// not shutting down.
+///
+/// Only the overall status is returned, as the route is public and
+/// save errors may reveal paths. Failed checks are logged instead.
+///
+/// Returns 200 OK and `{"status": "ready"}` if every check passed.
+/// Returns 503 SERVICE UNAVAILABLE and `{"status": "degraded"}`
+/// otherwise.
+pub async fn readyz(State(db): State<SharedAppState>) -> (StatusCode, Json<Value>) {
+    let health = db.health();
+    let loaded = health.loaded();
+    let writable = health.data_dir_writable();
+    let save_error = health.save_error();
+    let shutting_down = health.shutting_down();
+    let ready = loaded && writable && save_error.is_none() && !shutting_down;
+    if ready {
+        return (StatusCode::OK, Json(json!({ "status": "ready" })));
+    }
+    warn!(
+        "not ready: database loaded: {loaded}, data directory writable: {writable}, \
+         last save error: {save_error:?}, shutting down: {shutting_down}"
+    );
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({ "status": "degraded" })),
+    )
+}
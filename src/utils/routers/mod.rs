@@ -1,7 +1,15 @@
-//! Routers for GET, POST, PUT, and DELETE, and a fallback router.
+//! Routers for GET, POST, PUT, and DELETE, play and clear
+//! statistics, replays, revisions, reports, creator accounts,
+//! health checks, and a fallback router.
 
+pub mod creator;
 pub mod delete;
 pub mod get;
+pub mod health;
 pub mod post;
 pub mod put;
+pub mod replay;
+pub mod report;
+pub mod revision;
+pub mod stats;
 pub mod teapot;
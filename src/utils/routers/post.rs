@@ -1,41 +1,132 @@
 //! Routers for the POST HTTP method.
 
+use crate::error::{wants_json, Details};
 use crate::prelude::*;
+use crate::utils::creator::authenticate;
+// for documentation
+#[allow(unused_imports)]
+use crate::utils::{
+    config::Quotas, creator::Creator, duplicate::DuplicatePolicy, filter::WordList,
+};
 use axum::{
     extract::{ConnectInfo, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
 };
-use std::net::SocketAddr;
-use tracing::info;
+use itertools::Itertools;
+use serde_json::json;
+use std::net::{Ipv4Addr, SocketAddr};
+use tracing::{debug, info};
 
 /// Stages a level for uploading (if valid) and returns
 /// its key. An anti-orphan check [`orphanage`] is necessary.
 ///
+/// The key is only ever sent back this once, as Voyager
+/// only stores its hash. The level also gets a public ID.
+///
 /// See [`Data`] for details on level format.
 ///
+/// Levels are limited by daily and live quotas (see [`Quotas`]), which
+/// are checked here and once more on adoption.
+///
+/// Depending on the configuration, levels whose tiles and objects are
+/// identical to an existing level's may be rejected (see [`DuplicatePolicy`]).
+///
+/// The name, description, and author are run through the word list
+/// (see [`WordList`]), which may mask words, or hold the level for review
+/// once it is adopted.
+///
+/// The upload may be signed with a creator's credentials (see
+/// [`Creator`]), in which case the level belongs to the creator.
+/// Signed levels must have the creator's brand.
+///
 /// Returns 201 CREATED and a ULID key if successful. Returns 400 BAD REQUEST if
-/// the level was invalid.
+/// the level was invalid (or contained a blocked word). Returns 401 UNAUTHORIZED
+/// on invalid creator credentials. Returns 403 FORBIDDEN if a signed level does not
+/// have the creator's brand. Returns 409 CONFLICT if the level was a duplicate.
+/// Returns 429 TOO MANY REQUESTS if a quota was exceeded.
 pub async fn post(
     State(db): State<SharedAppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     level: String,
 ) -> Result<(StatusCode, String)> {
     let addr = addr.ip();
-    info!("POST sent by {addr}: {level}");
+    info!("POST sent by {addr}");
+    debug!("POST sent by {addr}: {level}");
     if db.ip_is_banned(&addr) {
         return Err(Error::Banned);
     }
+    let creator = authenticate(&db, &headers).await?;
 
-    let level = Level::new(level, addr);
+    let (level, key) = Level::new(level, addr);
     let mut parsed = level.into_parsed()?;
     parsed.set_dates_to_now();
-    info!("POST completed:\n{parsed}");
+    db.check_quota(&addr, parsed.brand)?;
+    db.check_duplicate(&parsed)?;
+    db.filter(&mut parsed)?;
+    if creator
+        .as_ref()
+        .is_some_and(|creator| creator.brand != parsed.brand)
+    {
+        return Err(Error::NotOwner);
+    }
+    info!("POST completed for {}", parsed.id);
+    debug!("POST completed:\n{parsed}");
 
     let level = parsed.into_level();
-    let key = level.key.to_string();
-
+    let id = level.id;
     db.insert_orphan(level);
-    Ok((StatusCode::CREATED, key))
+    if let Some(creator) = creator {
+        db.set_owner(id, creator.brand);
+    }
+    Ok((StatusCode::CREATED, key.to_string()))
+}
+
+/// Validates a level as if it was uploaded, without uploading it: nothing
+/// is staged, and the uploader is not recorded. Unlike [`post`], every
+/// invalid field is reported at once (see [`Level::validate`]). Once every
+/// field is valid, the level is also checked against the word list and,
+/// depending on the configuration, for duplicates.
+///
+/// Every problem is sent as a line of plain text, or, to clients asking
+/// for JSON, as `{"valid": false, "errors": [...]}` (see [`Details`]).
+///
+/// Returns 200 OK if the level would be accepted. Returns
+/// 400 BAD REQUEST and every problem otherwise.
+pub async fn validate(
+    State(db): State<SharedAppState>,
+    headers: HeaderMap,
+    level: String,
+) -> Response {
+    info!("VALIDATE sent");
+    let (level, _) = Level::new(level, Ipv4Addr::UNSPECIFIED.into());
+    let errors = match level.validate() {
+        Ok(mut parsed) => [
+            db.check_duplicate(&parsed).err(),
+            db.filter(&mut parsed).err(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+        Err(errors) => errors,
+    };
+    let status = if errors.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::BAD_REQUEST
+    };
+    if wants_json(&headers) {
+        let errors = errors.iter().map(Error::details).collect::<Vec<Details>>();
+        let body = json!({ "valid": errors.is_empty(), "errors": errors });
+        return (status, Json(body)).into_response();
+    }
+    if errors.is_empty() {
+        return (status, "valid").into_response();
+    }
+    let lines = errors.iter().map(ToString::to_string).join("\n");
+    (status, lines).into_response()
 }
 
 /// Moves a level from the orphan list to the level list.
@@ -52,7 +143,8 @@ pub async fn post(
 ///
 /// Returns 200 OK if successful. Returns 400 BAD REQUEST on
 /// invalid key. Returns 404 NOT FOUND on valid key, but
-/// no matching level.
+/// no matching level. Returns 429 TOO MANY REQUESTS if a
+/// quota was exceeded since the level was staged.
 pub async fn orphanage(
     State(db): State<SharedAppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -61,8 +153,8 @@ pub async fn orphanage(
     let addr = addr.ip();
     info!("ADOPTION sent by {addr}");
 
-    let ssn = key.parse()?;
-    db.adopt_orphan(&ssn)?;
+    let ssn = db.find_orphan(&key.parse()?)?;
+    db.adopt_orphan(ssn)?;
 
     info!("ADOPTION successful!");
     Ok(StatusCode::OK)
@@ -1,6 +1,9 @@
 //! Router for the PUT HTTP method.
 
 use crate::prelude::*;
+// for documentation
+#[allow(unused_imports)]
+use crate::utils::{duplicate::DuplicatePolicy, filter::WordList};
 use axum::{
     extract::{ConnectInfo, State},
     http::StatusCode,
@@ -16,10 +19,21 @@ use tracing::{debug, info};
 ///
 /// `version|name|description|music|author|brand|burdens|tiles|objects|key`
 ///
+/// Depending on the configuration, edits making the level's tiles and
+/// objects identical to another level's may be rejected (see
+/// [`DuplicatePolicy`]).
+///
+/// The name, description, and author are run through the word list
+/// (see [`WordList`]), which may mask words, or hold the edit for review.
+/// With pre-moderation on, every edit is held for review, and the level
+/// stays as it was until the edit is approved. Levels held for review
+/// may be edited too, and stay held.
+///
 /// Returns 201 CREATED if successful. Returns 400 BAD REQUEST on invalid
-/// level data. Returns 401 UNAUTHORIZED on invalid key. Returns 404 NOT
+/// level data (or a blocked word). Returns 401 UNAUTHORIZED on invalid key. Returns 404 NOT
 /// FOUND on if somehow, the level data and key are valid, but the key is
-/// not associated with any uploaded level.
+/// not associated with any uploaded level. Returns 409 CONFLICT if the
+/// edit made the level a duplicate.
 pub async fn put(
     State(db): State<SharedAppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -27,21 +41,35 @@ pub async fn put(
 ) -> Result<StatusCode> {
     let addr = addr.ip();
     info!("PUT sent by {addr}");
-    debug!("PUT sent by {addr}: {input}");
+    // the key is left out, as it should never be stored
+    let data = input
+        .rsplit_once('|')
+        .map_or(input.as_str(), |(data, _)| data);
+    debug!("PUT sent by {addr}: {data}");
     if db.ip_is_banned(&addr) {
         return Err(Error::Banned);
     }
 
     // TODO: improve
-    let level = Level::new_from_put(&input, addr)?;
-    let key = level.key;
+    let level = Level::new_from_put(&input, addr, |key| db.find_key(key))?;
+    let id = level.id;
     let mut parsed = level.into_parsed()?;
+    db.check_duplicate(&parsed)?;
+    let held = db.filter(&mut parsed)?;
 
-    let old_level = db.get(&key)?;
+    let old_level = db.get(id).or_else(|_| db.get_pending(id))?;
     parsed.set_dates_to_now();
     parsed.set_uploaded_from(old_level)?;
     let level = parsed.into_level();
-    db.insert(level);
+    if held {
+        db.hold(level, "word list");
+    } else if db.is_pending(id) {
+        db.update_pending(level)?;
+    } else if db.premoderation() {
+        db.hold(level, "pre-moderation");
+    } else {
+        db.insert(level);
+    }
     info!("PUT success by {addr}.");
     Ok(StatusCode::OK)
 }
@@ -0,0 +1,68 @@
+//! Routers for replay submission and step-count leaderboards.
+
+use crate::prelude::*;
+use crate::utils::{creator::authenticate, replay::Record};
+// for documentation
+#[allow(unused_imports)]
+use crate::utils::creator::Creator;
+use axum::{
+    extract::{ConnectInfo, Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use std::net::SocketAddr;
+use tracing::info;
+
+/// Verifies a replay, and records it as the level's best step count
+/// if it is the best one yet.
+///
+/// The format is as follows:
+///
+/// `id|moves`
+///
+/// Where `moves` is a sequence of `U`, `D`, `L`, `R` (walk or turn),
+/// and `A` (use the void rod), e.g. `RRDDAUULLL`.
+///
+/// Replays signed with a creator's credentials (see [`Creator`]) are
+/// also put on the level's leaderboard, under the creator's brand.
+///
+/// Returns 200 OK and the verified step count if successful. Returns
+/// 400 BAD REQUEST on invalid input, or if the replay does not reach
+/// the exit. Returns 401 UNAUTHORIZED on invalid creator credentials.
+/// Returns 404 NOT FOUND on valid ID, but no matching level.
+pub async fn submit(
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    input: String,
+) -> Result<(StatusCode, String)> {
+    let addr = addr.ip();
+    info!("REPLAY sent by {addr}");
+    if db.ip_is_banned(&addr) {
+        return Err(Error::Banned);
+    }
+    let creator = authenticate(&db, &headers).await?;
+    let (id, moves) = input.split_once('|').ok_or(Error::InvalidStructure)?;
+    let id = id.parse()?;
+    let brand = creator.map(|creator| creator.brand);
+    let steps = db.submit_replay(id, brand, moves)?;
+    info!("REPLAY verified for {addr}: {steps} steps");
+    Ok((StatusCode::OK, steps.to_string()))
+}
+
+/// Returns a level's leaderboard as JSON, sorted by step count.
+///
+/// For example:
+///
+/// `[{"brand":2685020332,"steps":14,"date":"20240304"}]`
+///
+/// Returns 200 OK and the leaderboard. Returns 400 BAD REQUEST on invalid
+/// ID. Returns 404 NOT FOUND on valid ID, but no matching level.
+pub async fn leaderboard(
+    Path(id): Path<String>,
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<Json<Vec<Record>>> {
+    info!("GET leaderboard sent by {} for {id}", addr.ip());
+    Ok(Json(db.leaderboard(id.parse()?)?))
+}
@@ -0,0 +1,49 @@
+//! Router for reporting levels.
+
+use crate::prelude::*;
+use crate::utils::report::{Reason, MAX_COMMENT_LEN};
+use axum::{
+    extract::{ConnectInfo, Path, State},
+    http::StatusCode,
+};
+use std::net::SocketAddr;
+use tracing::info;
+
+/// Reports a level to the moderators.
+///
+/// Takes in a level's public ID, e.g.
+/// `/voyager/report/:id`, and a reason, optionally followed by
+/// a comment. The format is as follows:
+///
+/// `reason|comment`
+///
+/// The reason is one of `offensive`, `spam`, `broken`, `stolen`, or
+/// `other`. The comment may be at most [`MAX_COMMENT_LEN`] long. Each
+/// client may only report each level once, later reports are ignored.
+///
+/// Returns 204 NO CONTENT if successful. Returns 400 BAD REQUEST on
+/// invalid input. Returns 404 NOT FOUND on valid ID, but no matching level.
+pub async fn report(
+    Path(id): Path<String>,
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    input: String,
+) -> Result<StatusCode> {
+    let addr = addr.ip();
+    info!("REPORT sent by {addr} for {id}: {input}");
+    if db.ip_is_banned(&addr) {
+        return Err(Error::Banned);
+    }
+    let id = id.parse()?;
+    let (reason, comment) = input.split_once('|').unwrap_or((&input, ""));
+    let reason = Reason::try_from(reason.trim())?;
+    let comment = comment.trim();
+    if comment.len() > MAX_COMMENT_LEN {
+        return Err(Error::InvalidComment(StringError::TooLong {
+            max: MAX_COMMENT_LEN as u64,
+            found: comment.len() as u64,
+        }));
+    }
+    db.report(id, addr, reason, comment)?;
+    Ok(StatusCode::NO_CONTENT)
+}
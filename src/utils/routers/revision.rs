@@ -0,0 +1,80 @@
+//! Routers for level revision history.
+
+use crate::prelude::*;
+use crate::utils::revision::Diff;
+use axum::{
+    extract::{ConnectInfo, Path, State},
+    Json,
+};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use tracing::info;
+
+/// Parses a level's public ID, hiding levels hidden for
+/// having been reported too many times as if they didn't exist.
+fn visible(db: &SharedAppState, id: &str) -> Result<PublicId> {
+    let id = id.parse()?;
+    if db.is_hidden(id) {
+        return Err(Error::LevelNotFound);
+    }
+    Ok(id)
+}
+
+/// Returns every kept revision of a level as JSON, oldest first.
+/// The last revision is the level as it currently is.
+///
+/// For example:
+///
+/// `[{"revision":0,"edited":"20240304","data":"1|Zm9v|..."}]`
+///
+/// See [`Data`](crate::utils::level::Data) for details on level format.
+///
+/// Returns 200 OK and the revisions. Returns 400 BAD REQUEST on invalid
+/// ID. Returns 404 NOT FOUND on valid ID, but no matching level (or
+/// the level is hidden).
+pub async fn list(
+    Path(id): Path<String>,
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<Json<Value>> {
+    info!("GET revisions sent by {} for {id}", addr.ip());
+    let revisions = db
+        .revisions(visible(&db, &id)?)?
+        .into_iter()
+        .map(|revision| {
+            let data = revision.level.data.to_string();
+            let edited = revision
+                .level
+                .into_parsed()
+                .map(|level| level.edited.to_string())
+                .unwrap_or_default();
+            json!({
+                "revision": revision.number,
+                "edited": edited,
+                "data": data,
+            })
+        })
+        .collect();
+    Ok(Json(Value::Array(revisions)))
+}
+
+/// Returns the changes between two revisions of a level as JSON.
+///
+/// For example, for `/voyager/revisions/:id/0/1`:
+///
+/// `{"fields":[{"field":"name","from":"foo","to":"bar"}],"cells":[{"layer":"tiles","x":6,"y":3,"from":"fl","to":"pt"}]}`
+///
+/// Returns 200 OK and the changes. Returns 400 BAD REQUEST on invalid
+/// ID. Returns 404 NOT FOUND on valid ID, but no matching level
+/// or revision (or the level is hidden).
+pub async fn diff(
+    Path((id, from, to)): Path<(String, u32, u32)>,
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<Json<Diff>> {
+    info!("GET diff sent by {} for {id}", addr.ip());
+    let id = visible(&db, &id)?;
+    let from = db.revision(id, from)?.into_parsed()?;
+    let to = db.revision(id, to)?.into_parsed()?;
+    Ok(Json(Diff::new(&from, &to)))
+}
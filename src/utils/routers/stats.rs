@@ -0,0 +1,87 @@
+//! Routers for level play and clear statistics.
+
+use crate::prelude::*;
+// for documentation
+#[allow(unused_imports)]
+use crate::utils::routers::replay::submit;
+use axum::{
+    extract::{ConnectInfo, Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use tracing::info;
+
+/// Counts a play of a level.
+///
+/// Endless Void sends this when a level is started. Takes in a
+/// level's public ID. Plays by the same client are counted at
+/// most once per dedup window.
+///
+/// Returns 204 NO CONTENT if successful. Returns 400 BAD REQUEST on
+/// invalid ID. Returns 404 NOT FOUND on valid ID, but no matching level.
+pub async fn play(
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    id: String,
+) -> Result<StatusCode> {
+    let addr = addr.ip();
+    info!("PLAY sent by {addr} for {id}");
+    if db.ip_is_banned(&addr) {
+        return Err(Error::Banned);
+    }
+    let id = id.parse()?;
+    db.record_play(id, addr)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Counts a clear of a level.
+///
+/// Endless Void sends this when a level is cleared. Takes in a level's
+/// public ID. Anything after it (Endless Void sends `id|steps|burdens`)
+/// is ignored, as step counts can't be trusted without a replay: the
+/// best step count only comes from verified replays, see [`submit`].
+///
+/// Clears by the same client are counted at most once per dedup window.
+///
+/// Returns 204 NO CONTENT if successful. Returns 400 BAD REQUEST on
+/// invalid ID. Returns 404 NOT FOUND on valid ID, but no matching level.
+pub async fn clear(
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    input: String,
+) -> Result<StatusCode> {
+    let addr = addr.ip();
+    info!("CLEAR sent by {addr}: {input}");
+    if db.ip_is_banned(&addr) {
+        return Err(Error::Banned);
+    }
+    let id = input.split('|').next().unwrap_or_default().parse()?;
+    db.record_clear(id, addr)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Returns a level's statistics as JSON.
+///
+/// For example:
+///
+/// `{"plays":12,"clears":3,"clear_rate":0.25,"best_steps":41,"best_burdens":0}`
+///
+/// Returns 200 OK and the statistics. Returns 400 BAD REQUEST on invalid
+/// ID. Returns 404 NOT FOUND on valid ID, but no matching level.
+pub async fn get(
+    Path(id): Path<String>,
+    State(db): State<SharedAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<Json<Value>> {
+    info!("GET stats sent by {} for {id}", addr.ip());
+    let stats = db.stats(id.parse()?)?;
+    Ok(Json(json!({
+        "plays": stats.plays,
+        "clears": stats.clears,
+        "clear_rate": stats.clear_rate(),
+        "best_steps": stats.best_steps,
+        "best_burdens": stats.best_burdens,
+    })))
+}
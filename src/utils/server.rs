@@ -1,11 +1,36 @@
 //! Contains [`AppState`], related methods, and
 //! various Axum server-related functions.
 use crate::prelude::*;
-use crate::utils::{level::Validated, routers, webui};
+use crate::utils::{
+    config::Config,
+    creator::Creator,
+    duplicate::{Content, DuplicatePolicy, Fingerprint},
+    filter::WordList,
+    format::Format,
+    health::Health,
+    index::{Filter, Indexes},
+    level::{today, Author, Brand, LegacyLevel, Validated},
+    logging,
+    metrics::{self, Exporter},
+    pending::{Decision, Pending, MAX_DECISIONS},
+    quota::{DailyCount, Quota},
+    ratelimit::{rate_limit, RateLimiter},
+    replay::{self, Record, MAX_LEADERBOARD_LEN},
+    report::{Reason, Report},
+    revision::Revision,
+    routers,
+    snapshot::Snapshot,
+    stats::{self, Stats},
+    thumbnail::{self, Thumbnails},
+    webui,
+};
+use arc_swap::ArcSwap;
 use axum::{
     async_trait,
+    body::Bytes,
     http::StatusCode,
-    routing::{any, delete, get, post, put},
+    middleware,
+    routing::{any, delete, get, post, put, MethodRouter},
     Router,
 };
 use axum_login::login_required;
@@ -13,20 +38,26 @@ use axum_login::{
     tower_sessions::{MemoryStore, SessionManagerLayer},
     AuthManagerLayerBuilder, AuthUser, AuthnBackend, UserId,
 };
-use dashmap::{DashMap, DashSet};
+use dashmap::{mapref::entry::Entry, DashMap, DashSet};
 use inquire::{min_length, Password, Text};
-use password_auth::generate_hash;
+use itertools::Itertools;
+use password_auth::{generate_hash, verify_password};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::create_dir;
 use std::net::IpAddr;
 use std::{
     fs::{read, write},
     net::SocketAddr,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
+use time::OffsetDateTime;
 use tokio::signal;
-use tower_http::timeout::TimeoutLayer;
+use tower_http::{compression::CompressionLayer, timeout::TimeoutLayer};
 use tracing::{info, warn};
 
 // for documentation
@@ -40,27 +71,266 @@ pub type SharedAppState = Arc<AppState>;
 /// of levels and orphans respectively.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AppState {
-    /// Every key and its matching uploaded, validated level.
-    levels: DashMap<Key, Level<Validated>>,
-    /// Every key and its matching validated orphan (see [`orphanage`]).
-    orphans: DashMap<Key, Level<Validated>>,
+    /// Every public ID and its matching uploaded, validated level.
+    levels: DashMap<PublicId, Level<Validated>>,
+    /// Every public ID and its matching validated orphan (see [`orphanage`]).
+    orphans: DashMap<PublicId, Level<Validated>>,
+    /// Every orphan's public ID and when it was staged, as a Unix timestamp.
+    /// Orphans older than [`Config::orphan_ttl`] are purged.
+    staged: DashMap<PublicId, i64>,
+    banned_ips: DashSet<IpAddr>,
+    /// Every public ID and its matching play and clear statistics.
+    stats: DashMap<PublicId, Stats>,
+    /// Every public ID and its matching leaderboard of verified
+    /// replays, sorted by step count.
+    leaderboards: DashMap<PublicId, Vec<Record>>,
+    /// Every public ID and its matching previous revisions, oldest first.
+    revisions: DashMap<PublicId, Vec<Revision>>,
+    /// The sequence number of the latest mutation. Counts up
+    /// by one every time a level is added, edited, or deleted.
+    ///
+    /// Also serves as the database's generation, see [`Self::generation`].
+    sequence: AtomicU64,
+    /// When the latest mutation happened, as a Unix timestamp.
+    modified: AtomicI64,
+    /// Every public ID and the sequence number of its level's latest
+    /// addition or edit.
+    changes: DashMap<PublicId, u64>,
+    /// Every deleted level's public ID and its deletion, see [`Tombstone`].
+    /// Tombstones older than [`Config::tombstone_ttl`] are pruned.
+    tombstones: DashMap<PublicId, Tombstone>,
+    /// The sequence number of the newest pruned tombstone. Clients
+    /// that last synced before it may have missed deletions, so
+    /// [`Self::changes`] tells them to sync every level again.
+    horizon: AtomicU64,
+    /// Every IP adress and how many levels it uploaded today.
+    ip_uploads: DashMap<IpAddr, DailyCount>,
+    /// Every author brand and how many levels it uploaded today.
+    brand_uploads: DashMap<Brand, DailyCount>,
+    /// Every IP adress with an overridden quota, see [`Quota`].
+    quota_overrides: DashMap<IpAddr, Quota>,
+    /// Every public ID and its matching level held for review, see [`Pending`].
+    pending: DashMap<PublicId, Pending>,
+    /// Every public ID and its matching reports, see [`Report`].
+    reports: DashMap<PublicId, Vec<Report>>,
+    /// Every level hidden from the level list for having
+    /// been reported too many times, see [`Config::report_threshold`].
+    hidden: DashSet<PublicId>,
+    /// Whether adopted levels and edits are held for review before
+    /// being made public. Switchable from the Web UI.
+    premoderation: AtomicBool,
+    /// The latest moderator decisions on held levels, oldest
+    /// first, at most [`MAX_DECISIONS`].
+    decisions: Mutex<Vec<Decision>>,
+    /// Every author brand and its creator account, see [`Creator`].
+    creators: DashMap<Brand, Creator>,
+    /// Every public ID and the brand of the creator it belongs to.
+    owners: DashMap<PublicId, Brand>,
+    /// When each IP adress last had a play of each level counted.
+    /// Only kept in memory, see [`stats::DEDUP_WINDOW`].
+    #[serde(skip)]
+    recent_plays: DashMap<(PublicId, IpAddr), OffsetDateTime>,
+    /// When each IP adress last had a clear of each level counted.
+    /// Only kept in memory, see [`stats::DEDUP_WINDOW`].
+    #[serde(skip)]
+    recent_clears: DashMap<(PublicId, IpAddr), OffsetDateTime>,
+    /// Whether plays, clears, or replays were recorded since the database
+    /// was last saved. They are saved periodically, see [`upkeep`].
+    #[serde(skip)]
+    unsaved_stats: AtomicBool,
+    /// Whether saving is put off until a batch of
+    /// changes is done, see [`Self::batch`].
+    #[serde(skip)]
+    batching: AtomicBool,
+    /// Voyager's settings. Loaded separately, see [`Config`].
+    #[serde(skip)]
+    config: Config,
+    /// Held while levels are added, edited, or deleted, so that
+    /// [`Self::changes`] never sees a half-made mutation.
+    #[serde(skip)]
+    mutation: Mutex<()>,
+    /// The latest [`Self::possible_duplicates`], and the
+    /// [generation](Self::generation) they were found in.
+    #[serde(skip)]
+    duplicates: Mutex<Option<(u64, Vec<DuplicatePair>)>>,
+    /// Held while an orphan is adopted, so that two adoptions
+    /// never both pass the same quota check, see [`Self::adopt_orphan`].
+    #[serde(skip)]
+    adoption: Mutex<()>,
+    /// An immutable view of every level, which reads are served from.
+    /// Swapped for a new one after every mutation, see [`Snapshot`].
+    #[serde(skip)]
+    snapshot: ArcSwap<Snapshot>,
+    /// Secondary indexes over the levels. Updated along with
+    /// the levels, and rebuilt on load, see [`Indexes`].
+    #[serde(skip)]
+    indexes: Indexes,
+    /// The content filter. Loaded separately, and
+    /// reloadable from the Web UI, see [`WordList`].
+    #[serde(skip)]
+    words: ArcSwap<WordList>,
+    /// What the readiness check looks at, see [`Health`].
+    #[serde(skip)]
+    health: Health,
+}
+
+/// Two possibly duplicate levels' public IDs, the older level first,
+/// and their similarity. See [`AppState::possible_duplicates`].
+type DuplicatePair = (PublicId, PublicId, f64);
+
+/// A deleted (or hidden) level, as remembered for [`AppState::changes`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Tombstone {
+    /// The sequence number of the deletion.
+    sequence: u64,
+    /// When the level was deleted, as a Unix timestamp.
+    deleted: i64,
+}
+
+impl Tombstone {
+    /// A tombstone for a level deleted now.
+    fn new(sequence: u64) -> Self {
+        Self {
+            sequence,
+            deleted: OffsetDateTime::now_utc().unix_timestamp(),
+        }
+    }
+}
+
+/// Every change since a cursor, see [`AppState::changes`].
+pub struct Changes {
+    /// The cursor to get the next changes with.
+    pub cursor: u64,
+    /// Whether the cursor was too old to know every deletion since.
+    /// If so, [`Self::levels`] holds every level instead, and the
+    /// client should forget every level it has.
+    pub resync: bool,
+    /// Every level added or edited since the cursor.
+    pub levels: Vec<Level<Validated>>,
+    /// Every level deleted since the cursor.
+    pub deleted: Vec<PublicId>,
+}
+
+/// The database layout of Voyager 0.5.1 and earlier.
+///
+/// Only used to migrate an old database on load.
+#[derive(Deserialize)]
+struct LegacyAppState {
+    levels: DashMap<Key, LegacyLevel>,
+    orphans: DashMap<Key, LegacyLevel>,
     banned_ips: DashSet<IpAddr>,
 }
 
+impl From<LegacyAppState> for AppState {
+    fn from(legacy: LegacyAppState) -> Self {
+        // levels were stored by key, so give them public IDs
+        // and keep only the keys' hashes
+        let by_id = |levels: DashMap<Key, LegacyLevel>| {
+            levels
+                .into_iter()
+                .map(|(_, level)| Level::from(level))
+                .map(|level| (level.id, level))
+                .collect::<DashMap<PublicId, Level<Validated>>>()
+        };
+        let levels = by_id(legacy.levels);
+        let orphans = by_id(legacy.orphans);
+        // every existing level counts as one change
+        let changes = levels
+            .iter()
+            .zip(1..)
+            .map(|(level, sequence)| (level.id, sequence))
+            .collect::<DashMap<PublicId, u64>>();
+        // existing orphans count as staged now
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let staged = orphans
+            .iter()
+            .map(|orphan| (orphan.id, now))
+            .collect::<DashMap<PublicId, i64>>();
+        Self {
+            levels,
+            orphans,
+            staged,
+            banned_ips: legacy.banned_ips,
+            stats: DashMap::new(),
+            leaderboards: DashMap::new(),
+            revisions: DashMap::new(),
+            sequence: AtomicU64::new(changes.len() as u64),
+            modified: AtomicI64::new(OffsetDateTime::now_utc().unix_timestamp()),
+            changes,
+            tombstones: DashMap::new(),
+            horizon: AtomicU64::new(0),
+            ip_uploads: DashMap::new(),
+            brand_uploads: DashMap::new(),
+            quota_overrides: DashMap::new(),
+            pending: DashMap::new(),
+            reports: DashMap::new(),
+            hidden: DashSet::new(),
+            premoderation: AtomicBool::new(false),
+            decisions: Mutex::new(Vec::new()),
+            creators: DashMap::new(),
+            owners: DashMap::new(),
+            recent_plays: DashMap::new(),
+            recent_clears: DashMap::new(),
+            unsaved_stats: AtomicBool::new(false),
+            batching: AtomicBool::new(false),
+            config: Config::default(),
+            mutation: Mutex::new(()),
+            duplicates: Mutex::new(None),
+            adoption: Mutex::new(()),
+            snapshot: ArcSwap::default(),
+            indexes: Indexes::default(),
+            words: ArcSwap::default(),
+            health: Health::default(),
+        }
+    }
+}
+
 impl AppState {
     /// Creates a new, empty Voyager database.
     #[must_use]
-    fn new() -> SharedAppState {
-        Arc::new(Self {
+    fn new(config: Config) -> Self {
+        Self {
             levels: DashMap::new(),
             orphans: DashMap::new(),
+            staged: DashMap::new(),
             banned_ips: DashSet::new(),
-        })
+            stats: DashMap::new(),
+            leaderboards: DashMap::new(),
+            revisions: DashMap::new(),
+            sequence: AtomicU64::new(0),
+            modified: AtomicI64::new(OffsetDateTime::now_utc().unix_timestamp()),
+            changes: DashMap::new(),
+            tombstones: DashMap::new(),
+            horizon: AtomicU64::new(0),
+            ip_uploads: DashMap::new(),
+            brand_uploads: DashMap::new(),
+            quota_overrides: DashMap::new(),
+            pending: DashMap::new(),
+            reports: DashMap::new(),
+            hidden: DashSet::new(),
+            premoderation: AtomicBool::new(false),
+            decisions: Mutex::new(Vec::new()),
+            creators: DashMap::new(),
+            owners: DashMap::new(),
+            recent_plays: DashMap::new(),
+            recent_clears: DashMap::new(),
+            unsaved_stats: AtomicBool::new(false),
+            batching: AtomicBool::new(false),
+            config,
+            mutation: Mutex::new(()),
+            duplicates: Mutex::new(None),
+            adoption: Mutex::new(()),
+            snapshot: ArcSwap::default(),
+            indexes: Indexes::default(),
+            words: ArcSwap::default(),
+            health: Health::default(),
+        }
     }
 
     /// Attempts to load a Voyager database from
-    /// `./voyager.db`. If it fails (likely due
-    /// to it not yet existing), it instead creates
+    /// `./voyager.db`, along with the settings from
+    /// `voyager/config.toml` (see [`Config`]). If it fails
+    /// (likely due to it not yet existing), it instead creates
     /// a new one using `Self::new()`;
     ///
     /// # Panics
@@ -69,187 +339,1681 @@ impl AppState {
     /// data structure had a breaking change (or
     /// the file is corrupted).
     #[must_use]
-    fn load() -> SharedAppState {
+    pub fn load() -> SharedAppState {
+        let _ = create_dir("voyager");
+        let config = Config::load();
         let input = read("voyager/levels.db");
-        input.map_or_else(
+        let levels = input.map_or_else(
             |_| {
                 info!("Existing database not found!");
-                Self::new()
+                Self::new(config.clone())
             },
             |level| {
                 info!("Existing database found!");
                 Self::from(&level).expect("valid database file")
             },
-        )
+        );
+        let levels = Self { config, ..levels };
+        for level in &levels.levels {
+            levels.indexes.add(&level);
+        }
+        levels.refresh_snapshot();
+        levels.update_orphan_metric();
+        levels.reload_word_list();
+        levels.health.set_loaded();
+        Arc::new(levels)
     }
 
     /// Attempts to save itself to `./voyager.db`.
     /// If it fails (likely due to file permissions),
     /// it will log a warning and keep running.
+    ///
+    /// How long saving takes, and how big the database is,
+    /// are recorded as metrics, see [`metrics`](crate::utils::metrics).
+    ///
+    /// Does nothing during a [batch](Self::batch).
+    // precision loss only matters past 2^52 bytes
+    #[allow(clippy::cast_precision_loss)]
     fn save(&self) {
+        if self.batching.load(Ordering::SeqCst) {
+            return;
+        }
+        let start = std::time::Instant::now();
+        self.unsaved_stats.store(false, Ordering::SeqCst);
         match bincode::serialize(&self) {
             Ok(bytes) => {
+                let size = bytes.len();
                 if let Err(why) = write("voyager/levels.db", bytes) {
                     warn!("database could not be saved: {why}");
-                };
+                    self.health.record_save(Some(why.to_string()));
+                } else {
+                    self.health.record_save(None);
+                }
+                ::metrics::gauge!("voyager_save_bytes").set(size as f64);
+            }
+            Err(why) => {
+                warn!("database could not be serialized: {why}");
+                self.health.record_save(Some(why.to_string()));
             }
-            Err(why) => warn!("database could not be serialized: {why}"),
         }
+        ::metrics::histogram!("voyager_save_duration_seconds")
+            .record(start.elapsed().as_secs_f64());
     }
 
     /// Attempts to deserialize a Voyager database
-    /// from bytes.
+    /// from bytes, migrating it if it was saved by
+    /// an older version of Voyager.
     ///
     /// # Errors
     /// This function will return an error if
     /// deserializing it fails. Most likely, some
     /// data structure had a breaking change (or
     /// the file is corrupted).
-    fn from(level: &[u8]) -> Result<SharedAppState> {
-        let levels = bincode::deserialize(level)?;
-        Ok(Arc::new(levels))
+    fn from(level: &[u8]) -> Result<Self> {
+        let levels = bincode::deserialize::<Self>(level).or_else(|why| {
+            bincode::deserialize::<LegacyAppState>(level)
+                .map(|legacy| {
+                    info!("Migrating database from an older version...");
+                    legacy.into()
+                })
+                .map_err(|_| why)
+        })?;
+        Ok(levels)
     }
 
-    /// Inserts a level and its key and saves to a file.
+    /// Inserts a level and saves to a file.
+    ///
+    /// If a level with the same public ID already exists, it is
+    /// kept as a [`Revision`]. At most [`Config::max_revisions`]
+    /// revisions are kept per level.
     pub fn insert(&self, level: Level<Validated>) {
-        self.levels.insert(level.key, level);
+        let id = level.id;
+        let mutation = self.lock_mutation();
+        self.changes.insert(id, self.next_sequence());
+        let indexed = level.clone();
+        if let Some(old) = self.levels.insert(id, level) {
+            self.indexes.remove(&old);
+            let mut revisions = self.revisions.entry(id).or_default();
+            let number = revisions.last().map_or(0, |revision| revision.number + 1);
+            revisions.push(Revision { number, level: old });
+            let excess = revisions.len().saturating_sub(self.config.max_revisions);
+            revisions.drain(..excess);
+        }
+        self.indexes.add(&indexed);
+        self.refresh_snapshot();
+        drop(mutation);
         self.save();
     }
 
-    /// Inserts an orphan and its key and saves to a file.
+    /// Makes a batch of changes, such as an import, saving to a
+    /// file once they are done instead of after every change.
+    pub fn batch<T>(&self, changes: impl FnOnce() -> T) -> T {
+        self.batching.store(true, Ordering::SeqCst);
+        let result = changes();
+        self.batching.store(false, Ordering::SeqCst);
+        self.save();
+        result
+    }
+
+    /// Inserts an orphan and saves to a file.
     pub fn insert_orphan(&self, level: Level<Validated>) {
-        self.orphans.insert(level.key, level);
+        self.staged
+            .insert(level.id, OffsetDateTime::now_utc().unix_timestamp());
+        self.orphans.insert(level.id, level);
+        self.update_orphan_metric();
+        self.save();
+    }
+
+    /// Returns every orphan, parsed, and when it was staged,
+    /// oldest first.
+    #[must_use]
+    pub fn orphans(&self) -> Vec<(Parsed, OffsetDateTime)> {
+        self.orphans
+            .iter()
+            .filter_map(|orphan| {
+                let staged = self.staged.get(&orphan.id).map_or(0, |staged| *staged);
+                let staged = OffsetDateTime::from_unix_timestamp(staged).ok()?;
+                Some((orphan.clone().into_parsed().ok()?, staged))
+            })
+            .sorted_by_key(|(_, staged)| *staged)
+            .collect()
+    }
+
+    /// Deletes an orphan, if it exists.
+    pub fn purge_orphan(&self, input: PublicId) -> Result<()> {
+        self.orphans.remove(&input).ok_or(Error::LevelNotFound)?;
+        self.staged.remove(&input);
+        self.owners.remove(&input);
+        self.update_orphan_metric();
+        self.save();
+        Ok(())
+    }
+
+    /// Deletes every orphan staged more than [`Config::orphan_ttl`]
+    /// seconds ago, and returns how many were deleted.
+    pub fn purge_expired_orphans(&self) -> usize {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let ttl = i64::try_from(self.config.orphan_ttl).unwrap_or(i64::MAX);
+        let cutoff = now.saturating_sub(ttl);
+        let expired = self
+            .orphans
+            .iter()
+            .map(|orphan| orphan.id)
+            .filter(|id| *self.staged.entry(*id).or_insert(now) <= cutoff)
+            .collect::<Vec<PublicId>>();
+        for id in &expired {
+            self.orphans.remove(id);
+            self.staged.remove(id);
+            self.owners.remove(id);
+        }
+        self.staged.retain(|id, _| self.orphans.contains_key(id));
+        if !expired.is_empty() {
+            self.update_orphan_metric();
+            self.save();
+        }
+        expired.len()
+    }
+
+    /// Deletes every orphan, no matter how recently it was
+    /// staged, and returns how many were deleted.
+    pub fn purge_orphans(&self) -> usize {
+        let count = self.orphans.len();
+        for id in self
+            .orphans
+            .iter()
+            .map(|orphan| orphan.id)
+            .collect::<Vec<_>>()
+        {
+            self.orphans.remove(&id);
+            self.owners.remove(&id);
+        }
+        self.staged.clear();
+        self.update_orphan_metric();
         self.save();
+        count
+    }
+
+    /// The server's health, see [`Health`].
+    #[must_use]
+    pub const fn health(&self) -> &Health {
+        &self.health
+    }
+
+    /// Voyager's settings, see [`Config`].
+    #[must_use]
+    pub const fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Sets the `voyager_levels`, `voyager_pending`, `voyager_orphans`,
+    /// and `voyager_banned_ips` gauges to the current counts.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn record_counts(&self) {
+        ::metrics::gauge!("voyager_levels").set(self.levels.len() as f64);
+        ::metrics::gauge!("voyager_pending").set(self.pending.len() as f64);
+        ::metrics::gauge!("voyager_banned_ips").set(self.banned_ips.len() as f64);
+        self.update_orphan_metric();
     }
 
-    /// Checks if the database contains the specified key.
+    /// Sets the `voyager_orphans` gauge to the current orphan count.
+    #[allow(clippy::cast_precision_loss)]
+    fn update_orphan_metric(&self) {
+        ::metrics::gauge!("voyager_orphans").set(self.orphans.len() as f64);
+    }
+
+    /// Checks if the database contains a level with the public ID.
     #[must_use]
-    pub fn contains(&self, input: &Key) -> bool {
-        self.levels.contains_key(input)
+    pub fn contains(&self, input: PublicId) -> bool {
+        self.levels.contains_key(&input)
+    }
+
+    /// Returns the public ID of the level with the key,
+    /// whether it is public or held for review.
+    ///
+    /// # Errors
+    /// Returns [`Error::LevelNotFound`] if there is no such level.
+    pub fn find_key(&self, input: &Key) -> Result<PublicId> {
+        let hash = input.hash();
+        self.indexes
+            .by_key(&hash)
+            .or_else(|| {
+                self.pending
+                    .iter()
+                    .find(|pending| pending.level.key_hash == hash)
+                    .map(|pending| *pending.key())
+            })
+            .ok_or(Error::LevelNotFound)
+    }
+
+    /// Returns the public ID of the orphan with the key.
+    ///
+    /// # Errors
+    /// Returns [`Error::LevelNotFound`] if there is no such orphan.
+    pub fn find_orphan(&self, input: &Key) -> Result<PublicId> {
+        let hash = input.hash();
+        self.orphans
+            .iter()
+            .find(|orphan| orphan.key_hash == hash)
+            .map(|orphan| *orphan.key())
+            .ok_or(Error::LevelNotFound)
     }
 
     pub fn ip_is_banned(&self, input: &IpAddr) -> bool {
         self.banned_ips.contains(input)
     }
 
-    /// Moves a level and its key from the orphans list
+    /// Moves a level from the orphans list
     /// to the levels list, if found.
-    pub fn adopt_orphan(&self, input: &Key) -> Result<()> {
-        let (_, level) = self.orphans.remove(input).ok_or(Error::LevelNotFound)?;
-        self.insert(level);
+    ///
+    /// Counts towards the uploader's and the brand's daily quota,
+    /// see [`Self::check_quota`]. Adoptions happen one at a time,
+    /// so that quotas are checked and counted atomically.
+    pub fn adopt_orphan(&self, input: PublicId) -> Result<()> {
+        // the lock guards no data, so a poisoned lock is harmless
+        let _adoption = self
+            .adoption
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let orphan = self
+            .orphans
+            .get(&input)
+            .ok_or(Error::LevelNotFound)?
+            .clone();
+        let mut parsed = orphan.clone().into_parsed()?;
+        let brand = parsed.brand;
+        self.check_quota(&orphan.uploader, brand)?;
+        self.check_duplicate(&parsed)?;
+        let held = self.filter(&mut parsed)?;
+        self.orphans.remove(&input).ok_or(Error::LevelNotFound)?;
+        let level = parsed.into_level();
+        self.ip_uploads
+            .entry(level.uploader)
+            .or_default()
+            .increment();
+        self.brand_uploads.entry(brand).or_default().increment();
+        self.staged.remove(&input);
+        self.update_orphan_metric();
+        if held {
+            self.hold(level, "word list");
+        } else if self.premoderation() {
+            self.hold(level, "pre-moderation");
+        } else {
+            self.insert(level);
+        }
+        Ok(())
+    }
+
+    /// Checks whether the IP adress may upload another level with
+    /// the author brand, according to [`Config::quotas`] or its overridden
+    /// [`Quota`].
+    ///
+    /// # Errors
+    /// Returns [`Error::QuotaExceeded`] if it may not.
+    pub fn check_quota(&self, ip: &IpAddr, brand: Brand) -> Result<()> {
+        let quotas = &self.config.quotas;
+        let overridden = self.quota_overrides.get(ip).map(|quota| *quota);
+        let (daily, live) = overridden
+            .map_or((quotas.daily_per_ip, quotas.max_live_per_ip), |quota| {
+                (quota.daily, quota.live)
+            });
+        let uploads = self.ip_uploads.get(ip).map_or(0, |count| count.today());
+        if uploads >= daily {
+            return Err(Error::QuotaExceeded(QuotaError::DailyUploads {
+                max: daily,
+            }));
+        }
+        if overridden.is_none() {
+            let max = quotas.daily_per_brand;
+            let uploads = self
+                .brand_uploads
+                .get(&brand)
+                .map_or(0, |count| count.today());
+            if uploads >= max {
+                return Err(Error::QuotaExceeded(QuotaError::DailyBrandUploads { max }));
+            }
+        }
+        if self.indexes.by_uploader(ip).len() >= live {
+            return Err(Error::QuotaExceeded(QuotaError::LiveLevels { max: live }));
+        }
+        Ok(())
+    }
+
+    /// Forgets how many levels IP adresses and brands uploaded
+    /// on days before today, as those no longer count.
+    pub fn forget_old_uploads(&self) {
+        self.ip_uploads.retain(|_, count| count.today() > 0);
+        self.brand_uploads.retain(|_, count| count.today() > 0);
+    }
+
+    /// Returns the public ID of an existing level that the
+    /// given level is an exact duplicate of, if any.
+    #[must_use]
+    pub fn find_duplicate(&self, level: &Parsed) -> Option<PublicId> {
+        self.indexes
+            .by_fingerprint(&Fingerprint::new(level))
+            .into_iter()
+            .find(|id| *id != level.id)
+    }
+
+    /// Checks whether the level may be uploaded (or
+    /// edited), according to [`Config::duplicates`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Duplicate`] if the level is an exact duplicate
+    /// of an existing one, and duplicates are rejected.
+    pub fn check_duplicate(&self, level: &Parsed) -> Result<()> {
+        if self.config.duplicates == DuplicatePolicy::Reject {
+            if let Some(original) = self.find_duplicate(level) {
+                info!("{} is a duplicate of {original}", level.id);
+                return Err(Error::Duplicate);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns every pair of levels that are at least
+    /// [`Config::near_duplicate_similarity`] similar, along with
+    /// their similarity. The older level of each pair comes first,
+    /// and the most similar pairs come first.
+    #[must_use]
+    pub fn possible_duplicates(&self) -> Vec<(Parsed, Parsed, f64)> {
+        let generation = self.sequence.load(Ordering::SeqCst);
+        let mut cached = self
+            .duplicates
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let pairs = match &*cached {
+            Some((found, pairs)) if *found == generation => pairs.clone(),
+            _ => {
+                let pairs = self.find_possible_duplicates();
+                *cached = Some((generation, pairs.clone()));
+                pairs
+            }
+        };
+        drop(cached);
+        let parsed = |id| self.get(id).ok()?.into_parsed().ok();
+        pairs
+            .into_iter()
+            .filter_map(|(original, copy, similarity)| {
+                Some((parsed(original)?, parsed(copy)?, similarity))
+            })
+            .collect()
+    }
+
+    /// Finds every [possible duplicate](Self::possible_duplicates).
+    /// Exact duplicates are looked up by fingerprint, and only levels
+    /// with as many cells are compared for near-duplicates, as levels
+    /// of different sizes are never similar.
+    fn find_possible_duplicates(&self) -> Vec<DuplicatePair> {
+        let levels = self
+            .parsed_levels()
+            .into_iter()
+            .sorted_by_key(|level| (level.uploaded.to_string(), level.id))
+            .collect::<Vec<Parsed>>();
+        let order = levels
+            .iter()
+            .enumerate()
+            .map(|(index, level)| (level.id, index))
+            .collect::<HashMap<PublicId, usize>>();
+        // every pair by its levels' order, earlier level first
+        let mut pairs = HashMap::new();
+        for group in self.indexes.duplicate_groups() {
+            let group = group
+                .iter()
+                .filter_map(|id| order.get(id).copied())
+                .sorted()
+                .collect::<Vec<usize>>();
+            for (first, second) in group.into_iter().tuple_combinations() {
+                pairs.insert((first, second), 1.0);
+            }
+        }
+        let by_size = levels
+            .iter()
+            .map(Content::new)
+            .filter_map(|content| Some((content.cell_count()?, content)))
+            .into_group_map();
+        for contents in by_size.values() {
+            for (original, copy) in contents.iter().tuple_combinations() {
+                let key = (order[&original.id], order[&copy.id]);
+                if pairs.contains_key(&key) {
+                    continue;
+                }
+                let similarity = original.similarity(copy);
+                if similarity >= self.config.near_duplicate_similarity {
+                    pairs.insert(key, similarity);
+                }
+            }
+        }
+        pairs
+            .into_iter()
+            .sorted_by(|((a, b), x), ((c, d), y)| y.total_cmp(x).then((a, b).cmp(&(c, d))))
+            .map(|((first, second), similarity)| (levels[first].id, levels[second].id, similarity))
+            .collect()
+    }
+
+    /// Runs a level through the word list, see [`Parsed::filter`].
+    pub fn filter(&self, level: &mut Parsed) -> Result<bool> {
+        level.filter(&self.words.load())
+    }
+
+    /// Reloads the word list from its file, see [`WordList::load`].
+    pub fn reload_word_list(&self) {
+        self.words.store(Arc::new(WordList::load()));
+    }
+
+    /// How many words are in the word list.
+    #[must_use]
+    pub fn word_count(&self) -> usize {
+        self.words.load().count()
+    }
+
+    /// Holds a level (or an edit of one) for review instead of
+    /// making it public, and saves to a file. See [`Pending`].
+    pub fn hold(&self, level: Level<Validated>, reason: &str) {
+        info!("Holding {} for review: {reason}", level.id);
+        let reason = reason.to_string();
+        self.pending.insert(level.id, Pending { level, reason });
+        self.save();
+    }
+
+    /// Returns every level held for review, parsed,
+    /// and why it was held, oldest first.
+    #[must_use]
+    pub fn pending(&self) -> Vec<(Parsed, String)> {
+        self.pending
+            .iter()
+            .filter_map(|pending| {
+                let parsed = pending.level.clone().into_parsed().ok()?;
+                Some((parsed, pending.reason.clone()))
+            })
+            .sorted_by_key(|(level, _)| (level.uploaded.to_string(), level.id))
+            .collect()
+    }
+
+    /// Checks if the public ID belongs to a level held for review.
+    #[must_use]
+    pub fn is_pending(&self, input: PublicId) -> bool {
+        self.pending.contains_key(&input)
+    }
+
+    /// Get a clone of a level held for review, if it exists.
+    pub fn get_pending(&self, input: PublicId) -> Result<Level<Validated>> {
+        self.pending.get(&input).map_or_else(
+            || Err(Error::LevelNotFound),
+            |pending| Ok(pending.level.clone()),
+        )
+    }
+
+    /// Replaces a level held for review with an edit of
+    /// it, still held for the same reason.
+    pub fn update_pending(&self, level: Level<Validated>) -> Result<()> {
+        let id = level.id;
+        self.pending.get_mut(&id).ok_or(Error::LevelNotFound)?.level = level;
+        self.save();
+        Ok(())
+    }
+
+    /// Makes a level held for review public, recording
+    /// the moderator's decision.
+    pub fn approve(&self, input: PublicId, moderator: &str) -> Result<()> {
+        let (_, pending) = self.pending.remove(&input).ok_or(Error::LevelNotFound)?;
+        self.decide(&pending.level, true, moderator);
+        self.insert(pending.level);
+        Ok(())
+    }
+
+    /// Forgets a level held for review, recording
+    /// the moderator's decision.
+    pub fn reject(&self, input: PublicId, moderator: &str) -> Result<()> {
+        let (_, pending) = self.pending.remove(&input).ok_or(Error::LevelNotFound)?;
+        self.decide(&pending.level, false, moderator);
+        if !self.contains(input) {
+            self.owners.remove(&input);
+        }
+        self.save();
+        Ok(())
+    }
+
+    fn decide(&self, level: &Level<Validated>, approved: bool, moderator: &str) {
+        let name = level
+            .clone()
+            .into_parsed()
+            .map_or_else(|_| String::new(), |parsed| parsed.name.to_string());
+        info!(
+            "{moderator} {} {}",
+            if approved { "approved" } else { "rejected" },
+            level.id
+        );
+        let decision = Decision {
+            id: level.id,
+            name,
+            approved,
+            moderator: moderator.to_string(),
+            date: today(),
+        };
+        let mut decisions = self
+            .decisions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        decisions.push(decision);
+        let excess = decisions.len().saturating_sub(MAX_DECISIONS);
+        decisions.drain(..excess);
+    }
+
+    /// Returns the latest moderator decisions, newest first.
+    #[must_use]
+    pub fn decisions(&self, count: usize) -> Vec<Decision> {
+        self.decisions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .iter()
+            .rev()
+            .take(count)
+            .cloned()
+            .collect()
+    }
+
+    /// Whether adopted levels and edits are held
+    /// for review before being made public.
+    #[must_use]
+    pub fn premoderation(&self) -> bool {
+        self.premoderation.load(Ordering::SeqCst)
+    }
+
+    /// Turns holding adopted levels and edits for review on or off.
+    pub fn set_premoderation(&self, enabled: bool) {
+        info!(
+            "Pre-moderation turned {}",
+            if enabled { "on" } else { "off" }
+        );
+        self.premoderation.store(enabled, Ordering::SeqCst);
+        self.save();
+    }
+
+    /// Reports a level, unless the IP adress already reported it.
+    /// Hides the level once it reaches [`Config::report_threshold`]
+    /// reports.
+    pub fn report(
+        &self,
+        input: PublicId,
+        reporter: IpAddr,
+        reason: Reason,
+        comment: &str,
+    ) -> Result<()> {
+        if !self.contains(input) {
+            return Err(Error::LevelNotFound);
+        }
+        let count = {
+            let mut reports = self.reports.entry(input).or_default();
+            if reports.iter().any(|report| report.reporter == reporter) {
+                return Ok(());
+            }
+            reports.push(Report {
+                reporter,
+                reason,
+                comment: comment.to_string(),
+                date: today(),
+            });
+            reports.len()
+        };
+        let threshold = self.config.report_threshold;
+        if threshold > 0 && count >= threshold {
+            self.set_hidden(input, true);
+        }
+        self.save();
+        Ok(())
+    }
+
+    /// Returns every reported level, parsed, along with its reports and
+    /// whether it is hidden, the most reported levels first.
+    #[must_use]
+    pub fn reports(&self) -> Vec<(Parsed, Vec<Report>, bool)> {
+        self.reports
+            .iter()
+            .filter_map(|reports| {
+                let parsed = self.get(*reports.key()).ok()?.into_parsed().ok()?;
+                let hidden = self.hidden.contains(reports.key());
+                Some((parsed, reports.value().clone(), hidden))
+            })
+            .sorted_by_key(|(_, reports, _)| std::cmp::Reverse(reports.len()))
+            .collect()
+    }
+
+    /// Checks if a level is hidden for having been reported too
+    /// many times, see [`Config::report_threshold`].
+    #[must_use]
+    pub fn is_hidden(&self, input: PublicId) -> bool {
+        self.hidden.contains(&input)
+    }
+
+    /// Forgets every report of a level, and unhides it.
+    pub fn dismiss_reports(&self, input: PublicId) {
+        self.reports.remove(&input);
+        self.set_hidden(input, false);
+        self.save();
+    }
+
+    /// Hides or unhides a level from the level list, see
+    /// [`Self::hidden`]. To [`Self::changes`], hiding a level
+    /// looks like deleting it, and unhiding it like adding it.
+    pub fn set_hidden(&self, input: PublicId, hidden: bool) {
+        let mutation = self.lock_mutation();
+        let changed = if hidden {
+            self.hidden.insert(input)
+        } else {
+            self.hidden.remove(&input).is_some()
+        };
+        if changed {
+            let sequence = self.next_sequence();
+            if hidden {
+                self.tombstones.insert(input, Tombstone::new(sequence));
+            } else {
+                self.tombstones.remove(&input);
+                self.changes.insert(input, sequence);
+            }
+            self.refresh_snapshot();
+        }
+        drop(mutation);
+    }
+
+    /// Registers a creator account (see [`Creator`]), if the key belongs
+    /// to a level with the creator's brand. The level then belongs to
+    /// the creator.
+    ///
+    /// # Errors
+    /// Returns [`Error::LevelNotFound`] if there is no level with the
+    /// key, [`Error::NotOwner`] if it does not have the creator's brand,
+    /// or [`Error::CreatorExists`] if the brand already has an account.
+    pub fn register(&self, creator: Creator, key: &Key) -> Result<()> {
+        let brand = creator.brand;
+        let id = self.find_key(key)?;
+        let level = self.get(id).or_else(|_| self.get_pending(id))?;
+        if level.into_parsed()?.brand != brand {
+            return Err(Error::NotOwner);
+        }
+        match self.creators.entry(brand) {
+            Entry::Occupied(_) => return Err(Error::CreatorExists),
+            Entry::Vacant(entry) => {
+                info!("Registering creator {brand}...");
+                entry.insert(creator);
+            }
+        }
+        self.owners.insert(id, brand);
+        self.save();
+        Ok(())
+    }
+
+    /// Returns every creator account, oldest first.
+    #[must_use]
+    pub fn creators(&self) -> Vec<Creator> {
+        self.creators
+            .iter()
+            .map(|creator| creator.clone())
+            .sorted_by_key(|creator| (creator.registered.clone(), creator.brand.to_string()))
+            .collect()
+    }
+
+    /// Deletes the brand's creator account, and saves to a file.
+    /// Its levels are kept, but no longer belong to anyone.
+    ///
+    /// Returns whether the brand had a creator account.
+    pub fn revoke(&self, brand: Brand) -> bool {
+        if self.creators.remove(&brand).is_none() {
+            return false;
+        }
+        info!("Revoking creator {brand}...");
+        self.owners.retain(|_, owner| *owner != brand);
+        self.save();
+        true
+    }
+
+    /// Returns the brand's creator account, if the secret is theirs.
+    ///
+    /// # Errors
+    /// Returns [`Error::Unauthorized`] if the brand has no
+    /// creator account, or the secret is not theirs.
+    pub fn authenticate(&self, brand: Brand, secret: &str) -> Result<Creator> {
+        let creator = self
+            .creators
+            .get(&brand)
+            .map(|creator| creator.clone())
+            .ok_or(Error::Unauthorized)?;
+        if !creator.verify(secret) {
+            return Err(Error::Unauthorized);
+        }
+        Ok(creator)
+    }
+
+    /// Returns the brand of the creator a level belongs to, if any.
+    #[must_use]
+    pub fn owner(&self, input: PublicId) -> Option<Brand> {
+        self.owners.get(&input).map(|owner| *owner)
+    }
+
+    /// Makes a level belong to the creator with the brand.
+    pub fn set_owner(&self, input: PublicId, brand: Brand) {
+        self.owners.insert(input, brand);
+        self.save();
+    }
+
+    /// Returns the public IDs of every level belonging to the
+    /// creator with the brand, whether public or held for review.
+    #[must_use]
+    pub fn owned_by(&self, brand: Brand) -> Vec<PublicId> {
+        self.owners
+            .iter()
+            .filter(|owner| *owner.value() == brand)
+            .map(|owner| *owner.key())
+            .filter(|id| self.contains(*id) || self.is_pending(*id))
+            .sorted()
+            .collect()
+    }
+
+    /// Returns every level belonging to the creator with the brand,
+    /// as they would be listed. See [`Level::listed`].
+    #[must_use]
+    pub fn creator_levels(&self, brand: Brand) -> Vec<String> {
+        self.owned_by(brand)
+            .into_iter()
+            .filter_map(|id| self.get(id).or_else(|_| self.get_pending(id)).ok())
+            .map(|level| level.listed())
+            .collect()
+    }
+
+    /// Makes a level uploaded before its creator registered (or
+    /// without signing it) belong to them, given its key.
+    ///
+    /// # Errors
+    /// Returns [`Error::NotOwner`] if the level does not have the brand.
+    pub fn claim(&self, brand: Brand, key: &Key) -> Result<PublicId> {
+        let id = self.find_key(key)?;
+        let level = self.get(id).or_else(|_| self.get_pending(id))?;
+        if level.into_parsed()?.brand != brand {
+            return Err(Error::NotOwner);
+        }
+        self.set_owner(id, brand);
+        Ok(id)
+    }
+
+    /// Checks that a level belongs to the creator with the brand.
+    ///
+    /// # Errors
+    /// Returns [`Error::LevelNotFound`] if there is no such level, or
+    /// [`Error::NotOwner`] if it does not belong to the creator.
+    fn check_owner(&self, input: PublicId, brand: Brand) -> Result<()> {
+        if !self.contains(input) && !self.is_pending(input) {
+            return Err(Error::LevelNotFound);
+        }
+        match self.owners.get(&input) {
+            Some(owner) if *owner == brand => Ok(()),
+            _ => Err(Error::NotOwner),
+        }
+    }
+
+    /// Gives a creator's level a new key, so that they may edit and
+    /// delete it again after losing the old one. The old key stops
+    /// working.
+    ///
+    /// # Errors
+    /// Returns an error if the level does not belong to the creator.
+    pub fn recover(&self, brand: Brand, input: PublicId) -> Result<Key> {
+        self.check_owner(input, brand)?;
+        let key = Key::generate();
+        let mutation = self.lock_mutation();
+        if let Some(mut level) = self.levels.get_mut(&input) {
+            self.indexes.remove(&level);
+            level.key_hash = key.hash();
+            self.indexes.add(&level);
+        }
+        if let Some(mut pending) = self.pending.get_mut(&input) {
+            pending.level.key_hash = key.hash();
+        }
+        self.refresh_snapshot();
+        drop(mutation);
+        info!("Recovered level {input} for creator {brand}.");
+        self.save();
+        Ok(key)
+    }
+
+    /// Renames a creator, and the author of every level belonging to
+    /// them. Each level is edited as if by PUT, so it may be held for
+    /// review because of the word list.
+    ///
+    /// Returns how many levels were renamed.
+    ///
+    /// # Errors
+    /// Returns an error if the new author contains a blocked word,
+    /// in which case nothing is renamed.
+    pub fn rename(&self, brand: Brand, author: &Author) -> Result<usize> {
+        let mut renamed = Vec::new();
+        for id in self.owned_by(brand) {
+            let old = self.get(id).or_else(|_| self.get_pending(id))?;
+            let mut parsed = old.clone().into_parsed()?;
+            parsed.author = author.clone();
+            let held = self.filter(&mut parsed)?;
+            parsed.set_dates_to_now();
+            parsed.set_uploaded_from(old)?;
+            renamed.push((parsed.into_level(), held));
+        }
+        if let Some(mut creator) = self.creators.get_mut(&brand) {
+            creator.author = author.clone();
+        }
+        let count = renamed.len();
+        for (level, held) in renamed {
+            if held {
+                self.hold(level, "word list");
+            } else if self.is_pending(level.id) {
+                self.update_pending(level)?;
+            } else {
+                self.insert(level);
+            }
+        }
+        info!("Renamed creator {brand} across {count} levels.");
+        self.save();
+        Ok(count)
+    }
+
+    /// Deletes every level belonging to the creator with the brand,
+    /// returning how many were deleted. See [`Self::delete`].
+    pub fn delete_creator_levels(&self, brand: Brand) -> usize {
+        let deleted = self.delete_all(self.owned_by(brand));
+        info!("Deleted {deleted} levels of creator {brand}.");
+        deleted
+    }
+
+    /// Returns every IP adress with an overridden quota.
+    #[must_use]
+    pub fn quota_overrides(&self) -> Vec<(IpAddr, Quota)> {
+        self.quota_overrides
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .sorted_by_key(|(ip, _)| *ip)
+            .collect()
+    }
+
+    /// Overrides the quota of an IP adress.
+    pub fn override_quota(&self, ip: &str, quota: Quota) -> Result<()> {
+        self.quota_overrides.insert(ip.parse()?, quota);
+        self.save();
+        Ok(())
+    }
+
+    /// Removes the overridden quota of an IP adress, if it has one.
+    pub fn reset_quota(&self, ip: &str) -> Result<()> {
+        self.quota_overrides.remove(&ip.parse()?);
+        self.save();
         Ok(())
     }
 
     /// Get a clone of a level from the database, if it exists.
-    pub fn get(&self, input: &Key) -> Result<Level<Validated>> {
+    pub fn get(&self, input: PublicId) -> Result<Level<Validated>> {
         self.levels
-            .get(input)
+            .get(&input)
             .map_or_else(|| Err(Error::LevelNotFound), |level| Ok(level.clone()))
     }
 
-    /// Deletes a level from the database, if it exists.
-    pub fn delete(&self, input: &Key) -> Result<StatusCode> {
-        let deleted = self.levels.remove(input).is_some();
-        self.save();
-        if deleted {
+    /// Deletes a level (and its stats, leaderboard, revisions, and
+    /// reports) from the database, along with any version of it
+    /// held for review, if it exists.
+    pub fn delete(&self, input: PublicId) -> Result<StatusCode> {
+        if self.delete_all([input]) > 0 {
             Ok(StatusCode::NO_CONTENT)
         } else {
             Err(Error::LevelNotFound)
         }
     }
 
+    /// Deletes every given level like [`Self::delete`], rebuilding the
+    /// snapshot and saving to a file only once. Returns how many were
+    /// deleted.
+    pub fn delete_all(&self, input: impl IntoIterator<Item = PublicId>) -> usize {
+        let mutation = self.lock_mutation();
+        let mut deleted = 0;
+        let mut listed = false;
+        for id in input {
+            let was_pending = self.pending.remove(&id).is_some();
+            let was_listed = self.remove(id);
+            if was_pending || was_listed {
+                deleted += 1;
+            }
+            listed |= was_listed;
+        }
+        if listed {
+            self.refresh_snapshot();
+        }
+        drop(mutation);
+        self.save();
+        deleted
+    }
+
+    /// Removes a level and everything about it, without rebuilding
+    /// the snapshot or saving. Returns whether there was such a level.
+    ///
+    /// Must only be called while holding [`Self::lock_mutation`].
+    fn remove(&self, input: PublicId) -> bool {
+        self.stats.remove(&input);
+        self.leaderboards.remove(&input);
+        self.revisions.remove(&input);
+        self.reports.remove(&input);
+        self.owners.remove(&input);
+        let Some((_, level)) = self.levels.remove(&input) else {
+            return false;
+        };
+        self.indexes.remove(&level);
+        self.hidden.remove(&input);
+        self.changes.remove(&input);
+        self.tombstones
+            .insert(input, Tombstone::new(self.next_sequence()));
+        true
+    }
+
+    /// Bans an IP adress, and deletes every level it uploaded.
     pub fn ban(&self, input: &str) -> Result<()> {
         let ip = input.parse::<IpAddr>()?;
         self.banned_ips.insert(ip);
-        // clone because dashmap will deadlock otherwise
-        let levels = self.levels.clone();
-        for level in &levels {
-            if level.uploader == ip {
-                self.delete(&level.key)?;
-            }
+        let deleted = self.delete_all(self.indexes.by_uploader(&ip));
+        info!("Banned {ip} and deleted {deleted} levels.");
+        Ok(())
+    }
+
+    /// Unbans an IP adress. Its deleted levels stay deleted.
+    /// Returns whether it was banned.
+    pub fn unban(&self, input: &str) -> Result<bool> {
+        let ip = input.parse::<IpAddr>()?;
+        let unbanned = self.banned_ips.remove(&ip).is_some();
+        self.save();
+        Ok(unbanned)
+    }
+
+    /// Counts a play of a level, unless the same IP adress
+    /// already had one counted within the [`stats::DEDUP_WINDOW`].
+    ///
+    /// Only saved to a file periodically, see [`Self::save_stats`].
+    pub fn record_play(&self, input: PublicId, ip: IpAddr) -> Result<()> {
+        if !self.contains(input) {
+            return Err(Error::LevelNotFound);
+        }
+        if Self::first_in_window(&self.recent_plays, input, ip) {
+            self.stats.entry(input).or_default().plays += 1;
+            self.unsaved_stats.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    /// Counts a clear of a level, unless the same IP adress
+    /// already had one counted within the [`stats::DEDUP_WINDOW`].
+    ///
+    /// Only saved to a file periodically, see [`Self::save_stats`].
+    pub fn record_clear(&self, input: PublicId, ip: IpAddr) -> Result<()> {
+        if !self.contains(input) {
+            return Err(Error::LevelNotFound);
+        }
+        if Self::first_in_window(&self.recent_clears, input, ip) {
+            self.stats.entry(input).or_default().clears += 1;
+            self.unsaved_stats.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    /// Saves to a file if plays, clears, or replays were
+    /// recorded since the database was last saved.
+    pub fn save_stats(&self) {
+        if self.unsaved_stats.load(Ordering::SeqCst) {
+            self.save();
         }
+    }
+
+    /// Forgets plays and clears made before the [`stats::DEDUP_WINDOW`].
+    pub fn forget_old_plays(&self) {
+        self.recent_plays.retain(|_, at| stats::is_recent(*at));
+        self.recent_clears.retain(|_, at| stats::is_recent(*at));
+    }
+
+    /// Get a clone of a level's stats, if the level exists.
+    pub fn stats(&self, input: PublicId) -> Result<Stats> {
+        if !self.contains(input) {
+            return Err(Error::LevelNotFound);
+        }
+        Ok(self
+            .stats
+            .get(&input)
+            .map(|stats| stats.clone())
+            .unwrap_or_default())
+    }
+
+    /// Replaces a level's stats, e.g. when importing it,
+    /// and saves to a file.
+    pub fn set_stats(&self, input: PublicId, stats: Stats) {
+        self.stats.insert(input, stats);
         self.save();
+    }
+
+    /// Verifies a replay of a level and, if it reaches the exit, records
+    /// its step count as the level's best (see [`Stats::set_best`]) and,
+    /// if a brand is given, puts it on the level's leaderboard under it.
+    /// Only each brand's best step count, and only the
+    /// [`MAX_LEADERBOARD_LEN`] best brands, are kept.
+    ///
+    /// Returns the replay's step count. Only saved to a
+    /// file periodically, see [`Self::save_stats`].
+    pub fn submit_replay(&self, input: PublicId, brand: Option<Brand>, moves: &str) -> Result<u32> {
+        let level = self.get(input)?.into_parsed()?;
+        let steps = replay::verify(&level, moves)?;
+        if let Some(brand) = brand {
+            let mut leaderboard = self.leaderboards.entry(input).or_default();
+            match leaderboard.iter_mut().find(|record| record.brand == brand) {
+                Some(record) if record.steps <= steps => (),
+                Some(record) => {
+                    record.steps = steps;
+                    record.date = today();
+                }
+                None => leaderboard.push(Record {
+                    brand,
+                    steps,
+                    date: today(),
+                }),
+            }
+            leaderboard.sort_by_key(|record| record.steps);
+            leaderboard.truncate(MAX_LEADERBOARD_LEN);
+        }
+        self.stats
+            .entry(input)
+            .or_default()
+            .set_best(steps, level.burdens);
+        self.unsaved_stats.store(true, Ordering::SeqCst);
+        Ok(steps)
+    }
+
+    /// Get a clone of a level's leaderboard, if the level exists.
+    pub fn leaderboard(&self, input: PublicId) -> Result<Vec<Record>> {
+        if !self.contains(input) {
+            return Err(Error::LevelNotFound);
+        }
+        Ok(self
+            .leaderboards
+            .get(&input)
+            .map(|leaderboard| leaderboard.clone())
+            .unwrap_or_default())
+    }
+
+    /// Get clones of every revision of a level, oldest first.
+    /// The last revision is the level as it currently is.
+    pub fn revisions(&self, input: PublicId) -> Result<Vec<Revision>> {
+        let current = self.get(input)?;
+        let mut revisions = self
+            .revisions
+            .get(&input)
+            .map(|revisions| revisions.clone())
+            .unwrap_or_default();
+        let number = revisions.last().map_or(0, |revision| revision.number + 1);
+        revisions.push(Revision {
+            number,
+            level: current,
+        });
+        Ok(revisions)
+    }
+
+    /// Get a clone of a single revision of a level, if it exists.
+    pub fn revision(&self, input: PublicId, number: u32) -> Result<Level<Validated>> {
+        self.revisions(input)?
+            .into_iter()
+            .find(|revision| revision.number == number)
+            .map(|revision| revision.level)
+            .ok_or(Error::RevisionNotFound)
+    }
+
+    /// Replaces a level with one of its previous revisions. The
+    /// replaced version is itself kept as a new revision.
+    pub fn revert(&self, input: PublicId, number: u32) -> Result<()> {
+        let mut level = self.revision(input, number)?;
+        // the key may have changed since, see `Self::recover`
+        level.key_hash = self.get(input)?.key_hash;
+        info!("Reverting level {input} to revision {number}...");
+        self.insert(level);
         Ok(())
     }
 
+    /// Returns every level added or edited, and the public ID
+    /// of every level deleted, after the given sequence number.
+    /// Also returns the current sequence number, to be used as
+    /// the next call's `since`.
+    ///
+    /// If tombstones newer than `since` were already pruned (see
+    /// [`Self::prune_tombstones`]), returns every level instead,
+    /// and marks the changes as a resync.
+    pub fn changes(&self, since: u64) -> Changes {
+        let _mutation = self.lock_mutation();
+        let resync = since < self.horizon.load(Ordering::SeqCst);
+        let since = if resync { 0 } else { since };
+        let levels = self
+            .changes
+            .iter()
+            .filter(|change| *change.value() > since)
+            .filter(|change| !self.hidden.contains(change.key()))
+            .filter_map(|change| self.levels.get(change.key()).map(|level| level.clone()))
+            .collect();
+        let deleted = if resync {
+            Vec::new()
+        } else {
+            self.tombstones
+                .iter()
+                .filter(|tombstone| tombstone.sequence > since)
+                .map(|tombstone| *tombstone.key())
+                .collect()
+        };
+        Changes {
+            cursor: self.sequence.load(Ordering::SeqCst),
+            resync,
+            levels,
+            deleted,
+        }
+    }
+
+    /// Forgets every tombstone older than [`Config::tombstone_ttl`]
+    /// seconds, moving the [horizon](Self::horizon) past them.
+    /// Returns how many were forgotten.
+    pub fn prune_tombstones(&self) -> usize {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let ttl = i64::try_from(self.config.tombstone_ttl).unwrap_or(i64::MAX);
+        let cutoff = now.saturating_sub(ttl);
+        let mutation = self.lock_mutation();
+        let expired = self
+            .tombstones
+            .iter()
+            .filter(|tombstone| tombstone.deleted < cutoff)
+            .map(|tombstone| (*tombstone.key(), tombstone.sequence))
+            .collect::<Vec<(PublicId, u64)>>();
+        for (id, sequence) in &expired {
+            self.tombstones.remove(id);
+            self.horizon.fetch_max(*sequence, Ordering::SeqCst);
+        }
+        drop(mutation);
+        if !expired.is_empty() {
+            self.save();
+        }
+        expired.len()
+    }
+
+    /// Returns the database's generation (the current sequence number)
+    /// and when it last changed, for HTTP caching of the level listing.
+    #[must_use]
+    pub fn generation(&self) -> (u64, OffsetDateTime) {
+        let modified = self.modified.load(Ordering::SeqCst);
+        (
+            self.sequence.load(Ordering::SeqCst),
+            OffsetDateTime::from_unix_timestamp(modified).unwrap_or(OffsetDateTime::UNIX_EPOCH),
+        )
+    }
+
+    /// Counts up the sequence number, returning the new one.
+    fn next_sequence(&self) -> u64 {
+        self.modified
+            .store(OffsetDateTime::now_utc().unix_timestamp(), Ordering::SeqCst);
+        self.sequence.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Locks out other mutations and [`Self::changes`]
+    /// until the returned guard is dropped.
+    fn lock_mutation(&self) -> std::sync::MutexGuard<'_, ()> {
+        // the lock guards no data, so a poisoned lock is harmless
+        self.mutation
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Remembers that an IP adress did something with a level now,
+    /// returning `false` if it already did within the [`stats::DEDUP_WINDOW`].
+    fn first_in_window(
+        recent: &DashMap<(PublicId, IpAddr), OffsetDateTime>,
+        id: PublicId,
+        ip: IpAddr,
+    ) -> bool {
+        match recent.entry((id, ip)) {
+            Entry::Occupied(entry) if stats::is_recent(*entry.get()) => false,
+            Entry::Occupied(mut entry) => {
+                entry.insert(OffsetDateTime::now_utc());
+                true
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(OffsetDateTime::now_utc());
+                true
+            }
+        }
+    }
+
     /// Returns a comma-separated lists of all stored levels.
     ///
     /// See [`Data`] for details on level format.
     #[must_use]
-    pub fn levels(&self) -> String {
-        self.levels
-            .clone()
-            .into_read_only()
-            .values()
-            .map(|level| level.data.to_string())
-            .collect::<Vec<String>>()
-            .join(",")
+    pub fn levels(&self) -> Bytes {
+        self.snapshot.load().listing()
     }
 
-    // TODO: this function is a whole mess!
     #[must_use]
     pub fn parsed_levels(&self) -> Vec<Parsed> {
-        self.levels
-            .clone()
-            .into_read_only()
-            .values()
-            .cloned()
-            .filter_map(|level| level.into_parsed().ok())
+        self.snapshot
+            .load()
+            .levels()
+            .iter()
+            .filter_map(|level| level.clone().into_parsed().ok())
             .collect::<Vec<Parsed>>()
     }
+
+    /// Returns a comma-separated list of the stored levels
+    /// matching the filter. See [`Self::levels`].
+    pub fn levels_matching(&self, filter: &Filter) -> Result<Bytes> {
+        let Some(ids) = self.indexes.find(filter)? else {
+            return Ok(self.levels());
+        };
+        let listing = ids
+            .iter()
+            .filter(|id| !self.hidden.contains(id))
+            .filter_map(|id| self.levels.get(id).map(|level| level.data.to_string()))
+            .join(",");
+        Ok(Bytes::from(listing))
+    }
+
+    /// Like [`Self::levels_matching`], but with every level in the given
+    /// format version, converted if it was uploaded in another one, and
+    /// followed by its public ID (see [`Level::listed`]). Levels that
+    /// cannot be converted (see [`Format::represents`]) are left out.
+    pub fn levels_matching_in(&self, filter: &Filter, format: Format) -> Result<Bytes> {
+        let ids = self.indexes.find(filter)?;
+        let listing = self
+            .snapshot
+            .load()
+            .levels()
+            .iter()
+            .filter(|level| !self.hidden.contains(&level.id))
+            .filter(|level| ids.as_ref().is_none_or(|ids| ids.contains(&level.id)))
+            .filter_map(|level| {
+                // only levels in another version need parsing
+                if level.format() == Some(format) {
+                    return Some(level.listed());
+                }
+                let parsed = level.clone().into_parsed().ok()?;
+                format
+                    .represents(&parsed)
+                    .then(|| format!("{}|{}", format.encode(&parsed), level.id))
+            })
+            .join(",");
+        Ok(Bytes::from(listing))
+    }
+
+    /// Returns every stored level matching the filter, parsed.
+    pub fn parsed_levels_matching(&self, filter: &Filter) -> Result<Vec<Parsed>> {
+        let Some(ids) = self.indexes.find(filter)? else {
+            return Ok(self.parsed_levels());
+        };
+        Ok(ids
+            .iter()
+            .filter_map(|id| self.get(*id).ok()?.into_parsed().ok())
+            .collect())
+    }
+
+    /// Rebuilds the [`Snapshot`] from the levels and swaps it in.
+    ///
+    /// Should be called while holding [`Self::lock_mutation`],
+    /// so that snapshots are swapped in the order they were made.
+    fn refresh_snapshot(&self) {
+        let levels = self
+            .levels
+            .iter()
+            .map(|level| level.clone())
+            .collect::<Vec<Level<Validated>>>();
+        self.snapshot
+            .store(Arc::new(Snapshot::new(levels, &self.hidden)));
+    }
+}
+
+/// How often [`upkeep`] runs.
+const UPKEEP_INTERVAL: Duration = Duration::from_mins(1);
+
+/// Periodically does the housekeeping nothing else does. Runs forever:
+/// - purges orphans older than [`Config::orphan_ttl`],
+///   see [`AppState::purge_expired_orphans`]
+/// - prunes tombstones older than [`Config::tombstone_ttl`],
+///   see [`AppState::prune_tombstones`]
+/// - forgets old plays and clears, see [`AppState::forget_old_plays`]
+/// - forgets old daily upload counts, see [`AppState::forget_old_uploads`]
+/// - saves recorded plays, clears, and replays, see [`AppState::save_stats`]
+async fn upkeep(db: SharedAppState) {
+    let mut interval = tokio::time::interval(UPKEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        let purged = db.purge_expired_orphans();
+        if purged > 0 {
+            info!("Purged {purged} expired orphans.");
+        }
+        let pruned = db.prune_tombstones();
+        if pruned > 0 {
+            info!("Pruned {pruned} expired tombstones.");
+        }
+        db.forget_old_plays();
+        db.forget_old_uploads();
+        db.save_stats();
+    }
 }
 
-/// Starts the Voyager server on port 3000.
+/// How often [`evict_idle_buckets`] looks for idle buckets.
+const BUCKET_EVICTION_INTERVAL: Duration = Duration::from_mins(1);
+
+/// Periodically forgets idle buckets of every [`RateLimiter`],
+/// see [`RateLimiter::evict_idle`]. Runs forever.
+async fn evict_idle_buckets(limiters: Vec<Arc<RateLimiter>>) {
+    let mut interval = tokio::time::interval(BUCKET_EVICTION_INTERVAL);
+    loop {
+        interval.tick().await;
+        for limiter in &limiters {
+            limiter.evict_idle();
+        }
+    }
+}
+
+/// Limits a route with a [`RateLimiter`], see [`rate_limit`].
+fn limited(
+    route: MethodRouter<SharedAppState>,
+    limiter: &Arc<RateLimiter>,
+) -> MethodRouter<SharedAppState> {
+    route.layer(middleware::from_fn_with_state(
+        Arc::clone(limiter),
+        rate_limit,
+    ))
+}
+
+/// Starts the Voyager server on port 3000, and the
+/// metrics' listener if configured (see [`Config::metrics`]).
 ///
 /// # Errors
 /// Returns an error if the app could not be served.
 pub async fn start_voyager() -> Result<()> {
+    let levels = AppState::load();
+    let exporter = Exporter::install(Arc::clone(&levels))?;
+    tokio::spawn(Arc::clone(&exporter).upkeep());
+    let config = &levels.config().metrics;
+    if let Some(addr) = config.listen {
+        let router = Router::new().route(
+            "/voyager/metrics",
+            get(metrics::render).with_state(Arc::clone(&exporter)),
+        );
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        info!("Metrics are now served on {addr}.");
+        tokio::spawn(async move {
+            axum::serve(listener, router)
+                .with_graceful_shutdown(shutdown_signal())
+                .await
+        });
+    }
+    let served = config.listen.is_none() && config.token.is_some();
+    let router = create_router(Arc::clone(&levels), served.then_some(exporter))?;
     info!("Voyager is now listening on port 3000.");
-    let router = create_router()?;
-    serve_app(router).await
+    serve_app(router, levels).await
 }
 
-/// Creates a new [`Router`] for Voyager.
-fn create_router() -> Result<Router> {
-    let _ = create_dir("voyager");
-    let levels = AppState::load();
+/// Creates the Web UI's [`Router`]. Everything but
+/// logging in requires being logged in.
+fn create_webui_router() -> Router<SharedAppState> {
+    Router::new()
+        .route("/voyager/webui", get(webui::index::index))
+        .route_layer(login_required!(Backend, login_url = "/voyager/webui/login"))
+        .route("/voyager/webui/delete/:id", post(webui::delete::delete))
+        .route_layer(login_required!(Backend, login_url = "/voyager/webui/login"))
+        .route("/voyager/webui/ban/:ip", post(webui::ban::ban))
+        .route_layer(login_required!(Backend, login_url = "/voyager/webui/login"))
+        .route("/voyager/webui/revisions/:id", get(webui::revision::list))
+        .route(
+            "/voyager/webui/diff/:id/:from/:to",
+            get(webui::revision::diff),
+        )
+        .route(
+            "/voyager/webui/revert/:id/:number",
+            post(webui::revision::revert),
+        )
+        .route_layer(login_required!(Backend, login_url = "/voyager/webui/login"))
+        .route("/voyager/webui/orphans", get(webui::orphan::list))
+        .route(
+            "/voyager/webui/orphans/adopt/:id",
+            post(webui::orphan::adopt),
+        )
+        .route(
+            "/voyager/webui/orphans/purge/:id",
+            post(webui::orphan::purge),
+        )
+        .route_layer(login_required!(Backend, login_url = "/voyager/webui/login"))
+        .route("/voyager/webui/reports", get(webui::report::list))
+        .route(
+            "/voyager/webui/reports/dismiss/:id",
+            post(webui::report::dismiss),
+        )
+        .route(
+            "/voyager/webui/reports/delete/:id",
+            post(webui::report::delete),
+        )
+        .route("/voyager/webui/reports/ban/:ip", post(webui::report::ban))
+        .route("/voyager/webui/pending", get(webui::pending::list))
+        .route(
+            "/voyager/webui/pending/approve/:id",
+            post(webui::pending::approve),
+        )
+        .route(
+            "/voyager/webui/pending/reject/:id",
+            post(webui::pending::reject),
+        )
+        .route(
+            "/voyager/webui/premoderation",
+            post(webui::pending::premoderation),
+        )
+        .route(
+            "/voyager/webui/wordlist/reload",
+            post(webui::pending::reload),
+        )
+        .route("/voyager/webui/duplicates", get(webui::duplicate::list))
+        .route("/voyager/webui/quotas", get(webui::quota::list))
+        .route("/voyager/webui/quotas", post(webui::quota::set))
+        .route("/voyager/webui/quotas/reset/:ip", post(webui::quota::reset))
+        .route_layer(login_required!(Backend, login_url = "/voyager/webui/login"))
+        .route("/voyager/webui/login", get(webui::login::get))
+        .route("/voyager/webui/login", post(webui::login::post))
+}
+
+/// Creates the [`Router`] for health checks (see [`routers::health`])
+/// and, if an [`Exporter`] is given, metrics. Never rate limited.
+fn create_monitoring_router(exporter: Option<Arc<Exporter>>) -> Router<SharedAppState> {
+    let router = Router::new()
+        .route("/voyager/healthz", get(routers::health::healthz))
+        .route("/voyager/readyz", get(routers::health::readyz));
+    match exporter {
+        Some(exporter) => router.route(
+            "/voyager/metrics",
+            get(metrics::render).with_state(exporter),
+        ),
+        None => router,
+    }
+}
+
+/// Creates the [`Router`] for creator accounts (see
+/// [`routers::creator`]), rate limited by the given [`RateLimiter`].
+fn create_creator_router(creator_limiter: &Arc<RateLimiter>) -> Router<SharedAppState> {
+    Router::new()
+        .route(
+            "/voyager/creator",
+            limited(post(routers::creator::register), creator_limiter),
+        )
+        .route(
+            "/voyager/creator/levels",
+            limited(
+                get(routers::creator::levels).delete(routers::creator::delete),
+                creator_limiter,
+            ),
+        )
+        .route(
+            "/voyager/creator/claim",
+            limited(post(routers::creator::claim), creator_limiter),
+        )
+        .route(
+            "/voyager/creator/recover/:id",
+            limited(post(routers::creator::recover), creator_limiter),
+        )
+        .route(
+            "/voyager/creator/rename",
+            limited(post(routers::creator::rename), creator_limiter),
+        )
+}
+
+/// Creates the [`Router`] for play and clear statistics (see
+/// [`routers::stats`]) and replays (see [`routers::replay`]).
+fn create_stats_router(
+    get_limiter: &Arc<RateLimiter>,
+    stats_limiter: &Arc<RateLimiter>,
+    replay_limiter: &Arc<RateLimiter>,
+) -> Router<SharedAppState> {
+    Router::new()
+        .route(
+            "/voyager/play",
+            limited(post(routers::stats::play), stats_limiter),
+        )
+        .route(
+            "/voyager/clear",
+            limited(post(routers::stats::clear), stats_limiter),
+        )
+        .route(
+            "/voyager/stats/:id",
+            limited(get(routers::stats::get), get_limiter),
+        )
+        .route(
+            "/voyager/replay",
+            limited(post(routers::replay::submit), replay_limiter),
+        )
+        .route(
+            "/voyager/leaderboard/:id",
+            limited(get(routers::replay::leaderboard), get_limiter),
+        )
+}
+
+/// Creates a new [`Router`] for Voyager, serving
+/// the metrics too if an [`Exporter`] is given.
+fn create_router(levels: SharedAppState, exporter: Option<Arc<Exporter>>) -> Result<Router> {
+    tokio::spawn(upkeep(Arc::clone(&levels)));
+
+    let access_log = levels.config().logging.access_log;
+    let limits = &levels.config().rate_limits;
+    // every limiter's idle buckets are evicted periodically
+    let mut limiters = Vec::new();
+    let mut limiter = |limit| {
+        let limiter = RateLimiter::new(limit);
+        limiters.push(Arc::clone(&limiter));
+        limiter
+    };
+    let post_limiter = limiter(limits.post);
+    let put_limiter = limiter(limits.put);
+    let delete_limiter = limiter(limits.delete);
+    let orphanage_limiter = limiter(limits.orphanage);
+    let get_limiter = limiter(limits.get);
+    let report_limiter = limiter(limits.report);
+    let creator_limiter = limiter(limits.creator);
+    let stats_limiter = limiter(limits.stats);
+    let replay_limiter = limiter(limits.replay);
+    let thumbnail_limiter = limiter(limits.thumbnail);
+    tokio::spawn(evict_idle_buckets(limiters));
 
     let session_store = MemoryStore::default();
     let session_layer = SessionManagerLayer::new(session_store).with_secure(false);
 
+    let thumbnails = Thumbnails::new(Arc::clone(&levels));
     let backend = Backend::load()?;
     let auth_layer = AuthManagerLayerBuilder::new(backend, session_layer).build();
 
     Ok(Router::new()
-        .route("/voyager/webui", get(webui::index::index))
-        .route_layer(login_required!(Backend, login_url = "/voyager/webui/login"))
-        .route("/voyager/webui/delete/:key", post(webui::delete::delete))
-        .route_layer(login_required!(Backend, login_url = "/voyager/webui/login"))
-        .route("/voyager/webui/ban/:ip", post(webui::ban::ban))
-        .route_layer(login_required!(Backend, login_url = "/voyager/webui/login"))
-        .route("/voyager/webui/login", get(webui::login::get))
-        .route("/voyager/webui/login", post(webui::login::post))
-        .route("/voyager", get(routers::get::get))
-        .route("/voyager/:keys", get(routers::get::levels_exist))
-        .route("/voyager", post(routers::post::post))
-        .route("/voyager/orphanage", post(routers::post::orphanage))
-        .route("/voyager", put(routers::put::put))
-        .route("/voyager", delete(routers::delete::delete))
+        .merge(create_webui_router())
+        .merge(create_monitoring_router(exporter))
+        .merge(create_creator_router(&creator_limiter))
+        .merge(create_stats_router(
+            &get_limiter,
+            &stats_limiter,
+            &replay_limiter,
+        ))
+        .route("/voyager", limited(get(routers::get::get), &get_limiter))
+        .route(
+            "/voyager/:keys",
+            limited(get(routers::get::levels_exist), &get_limiter),
+        )
+        .route(
+            "/voyager",
+            limited(post(routers::post::post), &post_limiter),
+        )
+        .route(
+            "/voyager/validate",
+            limited(post(routers::post::validate), &get_limiter),
+        )
+        .route(
+            "/voyager/orphanage",
+            limited(post(routers::post::orphanage), &orphanage_limiter),
+        )
+        .route(
+            "/voyager/changes",
+            limited(get(routers::get::changes), &get_limiter),
+        )
+        .route(
+            "/voyager/pending/:key",
+            limited(get(routers::get::pending), &get_limiter),
+        )
+        .route(
+            "/voyager/report/:id",
+            limited(post(routers::report::report), &report_limiter),
+        )
+        .route(
+            "/voyager/thumb/:file",
+            limited(
+                get(thumbnail::thumbnail).with_state(thumbnails),
+                &thumbnail_limiter,
+            ),
+        )
+        .route(
+            "/voyager/revisions/:id",
+            limited(get(routers::revision::list), &get_limiter),
+        )
+        .route(
+            "/voyager/revisions/:id/:from/:to",
+            limited(get(routers::revision::diff), &get_limiter),
+        )
+        .route("/voyager", limited(put(routers::put::put), &put_limiter))
+        .route(
+            "/voyager",
+            limited(delete(routers::delete::delete), &delete_limiter),
+        )
         .route("/voyager", any(routers::teapot::teapot))
         .with_state(levels)
+        .layer(middleware::from_fn(crate::error::negotiate))
         .layer(TimeoutLayer::new(Duration::from_secs(10)))
-        .layer(auth_layer))
+        .layer(CompressionLayer::new())
+        .layer(auth_layer)
+        .layer(middleware::from_fn(metrics::track))
+        .layer(middleware::from_fn_with_state(
+            access_log,
+            logging::trace_request,
+        )))
 }
 
-/// Serves the Voyager app on port 3000.
-async fn serve_app(app: Router) -> Result<()> {
+/// Serves the Voyager app on port 3000. Once asked to shut down,
+/// the server is marked as no longer ready (see [`Health`]), and
+/// counted plays and clears are saved once it has shut down.
+async fn serve_app(app: Router, levels: SharedAppState) -> Result<()> {
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
+    let db = Arc::clone(&levels);
     axum::serve(
         listener,
         app.into_make_service_with_connect_info::<SocketAddr>(),
     )
-    .with_graceful_shutdown(shutdown_signal())
+    .with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        info!("Voyager is shutting down.");
+        db.health().set_shutting_down();
+        // keeps serving until load balancers have seen it is not ready
+        let delay = db.config().shutdown_delay;
+        tokio::time::sleep(Duration::from_secs(delay)).await;
+    })
     .await?;
+    levels.save_stats();
     Ok(())
 }
 
@@ -322,7 +2086,7 @@ impl Backend {
             Ok(bytes) => {
                 if let Err(why) = write("voyager/webui.db", bytes) {
                     warn!("webui could not be saved: {why}");
-                };
+                }
             }
             Err(why) => warn!("webui could not be serialized: {why}"),
         }
@@ -333,13 +2097,86 @@ impl Backend {
         Ok(webui)
     }
 
+    /// Loads the Web UI users without asking for
+    /// one if there are none, unlike [`Self::load`].
+    pub fn open() -> Result<Self> {
+        read("voyager/webui.db").map_or_else(|_| Ok(Self::default()), |webui| Self::from(&webui))
+    }
+
+    /// Every Web UI user's username, sorted.
+    #[must_use]
+    pub fn usernames(&self) -> Vec<String> {
+        self.users
+            .values()
+            .map(|user| user.username.clone())
+            .sorted()
+            .collect()
+    }
+
+    /// Adds a Web UI user and saves to a file.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidUser`] if the username or password is too
+    /// short, or [`Error::UserExists`] if the username is taken.
+    pub fn add_user(&mut self, username: &str, password: &str) -> Result<()> {
+        check_user(username, password)?;
+        if self.find_user(username).is_some() {
+            return Err(Error::UserExists);
+        }
+        let id = self.users.keys().max().map_or(1, |id| id + 1);
+        self.users.insert(
+            id,
+            User {
+                id,
+                username: username.to_string(),
+                password_hash: generate_hash(password),
+            },
+        );
+        self.save();
+        Ok(())
+    }
+
+    /// Removes a Web UI user and saves to a file.
+    ///
+    /// # Errors
+    /// Returns [`Error::UserNotFound`] if there is no such user.
+    pub fn remove_user(&mut self, username: &str) -> Result<()> {
+        let id = self.find_user(username).ok_or(Error::UserNotFound)?;
+        self.users.remove(&id);
+        self.save();
+        Ok(())
+    }
+
+    /// Changes a Web UI user's password, logging them
+    /// out everywhere, and saves to a file.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidUser`] if the password is too short,
+    /// or [`Error::UserNotFound`] if there is no such user.
+    pub fn set_password(&mut self, username: &str, password: &str) -> Result<()> {
+        check_user(username, password)?;
+        let id = self.find_user(username).ok_or(Error::UserNotFound)?;
+        if let Some(user) = self.users.get_mut(&id) {
+            user.password_hash = generate_hash(password);
+        }
+        self.save();
+        Ok(())
+    }
+
+    fn find_user(&self, username: &str) -> Option<i64> {
+        self.users
+            .values()
+            .find(|user| user.username == username)
+            .map(|user| user.id)
+    }
+
     fn new() -> Result<Self> {
         println!("please create a user for the webui!");
         let username = Text::new("username:")
-            .with_validator(min_length!(2))
+            .with_validator(min_length!(MIN_USERNAME_LEN))
             .prompt()?;
         let password = Password::new("password:")
-            .with_validator(min_length!(8))
+            .with_validator(min_length!(MIN_PASSWORD_LEN))
             .prompt()?;
         let login = Self {
             users: std::collections::HashMap::from([(
@@ -356,6 +2193,19 @@ impl Backend {
     }
 }
 
+/// A Web UI username's min length.
+pub const MIN_USERNAME_LEN: usize = 2;
+
+/// A Web UI password's min length.
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+fn check_user(username: &str, password: &str) -> Result<()> {
+    if username.chars().count() < MIN_USERNAME_LEN || password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(Error::InvalidUser);
+    }
+    Ok(())
+}
+
 #[derive(Clone, Deserialize)]
 pub struct Credentials {
     pub username: String,
@@ -371,12 +2221,16 @@ impl AuthnBackend for Backend {
 
     async fn authenticate(
         &self,
-        Credentials { username, .. }: Self::Credentials,
+        Credentials {
+            username, password, ..
+        }: Self::Credentials,
     ) -> std::result::Result<Option<Self::User>, Self::Error> {
         Ok(self
             .users
             .values()
-            .find(|user| user.username == username)
+            .find(|user| {
+                user.username == username && verify_password(&password, &user.password_hash).is_ok()
+            })
             .cloned())
     }
 
@@ -0,0 +1,58 @@
+//! Contains [`Snapshot`], an immutable view of every level
+//! that reads are served from.
+
+use crate::prelude::*;
+use crate::utils::level::Validated;
+use axum::body::Bytes;
+use dashmap::DashSet;
+use itertools::Itertools;
+
+// for documentation
+#[allow(unused_imports)]
+use crate::utils::{level::Data, server::AppState};
+
+/// An immutable view of every level in the database.
+///
+/// [`AppState`] rebuilds it after every mutation and swaps it in
+/// atomically, so reads never have to lock (or clone) the levels
+/// themselves, and the listing is only built once per change.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    /// Every level, in no particular order.
+    levels: Vec<Level<Validated>>,
+    /// The comma-separated list of every public level's data.
+    ///
+    /// See [`Data`] for details on level format.
+    listing: Bytes,
+}
+
+impl Snapshot {
+    /// Creates a snapshot of the given levels, building the listing
+    /// of every level that is not hidden.
+    #[must_use]
+    pub fn new(levels: Vec<Level<Validated>>, hidden: &DashSet<PublicId>) -> Self {
+        let listing = levels
+            .iter()
+            .filter(|level| !hidden.contains(&level.id))
+            .map(|level| level.data.to_string())
+            .join(",");
+        Self {
+            levels,
+            listing: Bytes::from(listing),
+        }
+    }
+
+    /// Every level, in no particular order.
+    #[must_use]
+    pub fn levels(&self) -> &[Level<Validated>] {
+        &self.levels
+    }
+
+    /// The comma-separated list of every public level's data.
+    ///
+    /// Cloning it is cheap, as the underlying buffer is shared.
+    #[must_use]
+    pub fn listing(&self) -> Bytes {
+        self.listing.clone()
+    }
+}
@@ -0,0 +1,62 @@
+//! Contains [`Stats`], the per-level play and clear
+//! counters, and the deduplication window for them.
+
+use crate::utils::level::Burdens;
+// for documentation
+#[allow(unused_imports)]
+use crate::utils::replay::verify;
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+/// How long a client's play or clear of a level is remembered.
+///
+/// Repeated plays (or clears) of the same level by the same IP
+/// adress within this window are only counted once, so that
+/// restarting a level over and over doesn't inflate its stats.
+pub const DEDUP_WINDOW: Duration = Duration::hours(1);
+
+/// A level's play and clear statistics, as
+/// reported by Endless Void.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Stats {
+    /// How many times the level has been started.
+    pub plays: u64,
+    /// How many times the level has been cleared.
+    pub clears: u64,
+    /// The fewest steps the level has been cleared in by
+    /// a verified replay, if ever. See [`verify`].
+    pub best_steps: Option<u32>,
+    /// The level's burdens when the [`Self::best_steps`] replay was verified.
+    pub best_burdens: Option<Burdens>,
+}
+
+impl Stats {
+    /// The ratio of clears to plays, between 0 and 1.
+    ///
+    /// A level that has never been played has a clear rate of 0.
+    #[must_use]
+    // precision loss only matters past 2^52 plays
+    #[allow(clippy::cast_precision_loss)]
+    pub fn clear_rate(&self) -> f64 {
+        if self.plays == 0 {
+            return 0.0;
+        }
+        (self.clears as f64 / self.plays as f64).min(1.0)
+    }
+
+    /// Records a verified replay's step count, replacing
+    /// the best step count if this one is lower.
+    pub fn set_best(&mut self, steps: u32, burdens: Burdens) {
+        if self.best_steps.is_none_or(|best| steps < best) {
+            self.best_steps = Some(steps);
+            self.best_burdens = Some(burdens);
+        }
+    }
+}
+
+/// Whether a play or clear made at `at` is still
+/// within the [`DEDUP_WINDOW`].
+#[must_use]
+pub fn is_recent(at: OffsetDateTime) -> bool {
+    OffsetDateTime::now_utc() - at < DEDUP_WINDOW
+}
@@ -0,0 +1,335 @@
+//! Contains [`Thumbnails`], which draws levels as PNG images, and
+//! [`thumbnail`], the router serving them.
+//!
+//! Levels are drawn cell by cell, tiles first and objects on top,
+//! [`CELL_SIZE`] pixels per cell. Cells are drawn with the sprites of
+//! an optional tileset (see [`Tileset`]), and as blocks of color if
+//! there is no tileset or it has no sprite for them.
+
+use crate::prelude::*;
+use crate::utils::{
+    grid::{Cell, Grid, GRID_WIDTH},
+    level::Validated,
+};
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs::{read, read_to_string},
+    sync::Arc,
+};
+use tracing::{info, warn};
+
+/// How many pixels wide and tall every cell is drawn.
+pub const CELL_SIZE: usize = 16;
+
+/// The tileset image, see [`Tileset`].
+const TILESET_IMAGE: &str = "voyager/tileset.png";
+
+/// The ids of the tileset's sprites, see [`Tileset`].
+const TILESET_IDS: &str = "voyager/tileset.txt";
+
+/// How long clients may use a thumbnail before asking for it again.
+/// They are asked with the thumbnail's `ETag`, so that unchanged
+/// thumbnails are not sent again.
+const CACHE_CONTROL_VALUE: &str = "public, max-age=60";
+
+/// An RGBA pixel.
+type Pixel = [u8; 4];
+
+/// The sprites to draw cells with, loaded on startup from
+/// `voyager/tileset.png` and `voyager/tileset.txt`.
+///
+/// The image is a grid of [`CELL_SIZE`] by [`CELL_SIZE`] pixel sprites,
+/// read left to right, top to bottom. The text file names them in the
+/// same order, one per line, by cell (e.g. `wa16`) or by two-letter id
+/// (e.g. `wa`), which is used for every variant without a sprite of its own:
+///
+/// ```text
+/// pt
+/// fl
+/// wa
+/// wa16
+/// ```
+pub struct Tileset {
+    sprites: HashMap<String, Vec<Pixel>>,
+    /// A hash of the image and the ids, so that thumbnails
+    /// drawn with another tileset get another `ETag`.
+    hash: String,
+}
+
+/// A drawn thumbnail.
+#[derive(Clone)]
+struct Thumbnail {
+    /// A hash of the level's data and the tileset.
+    etag: String,
+    png: Bytes,
+}
+
+/// Draws thumbnails, and keeps the latest thumbnail of every level
+/// that was asked for. A level's thumbnail is drawn again once
+/// its data changes, i.e. once it is edited.
+pub struct Thumbnails {
+    db: SharedAppState,
+    tileset: Option<Tileset>,
+    cache: DashMap<PublicId, Thumbnail>,
+}
+
+impl Tileset {
+    /// Loads the tileset, if there is one.
+    ///
+    /// Logs a warning and returns `None` if it could not be loaded.
+    #[must_use]
+    pub fn load() -> Option<Self> {
+        let (Ok(image), Ok(ids)) = (read(TILESET_IMAGE), read_to_string(TILESET_IDS)) else {
+            info!("No tileset found, drawing thumbnails with colors.");
+            return None;
+        };
+        match Self::decode(&image, &ids) {
+            Ok(tileset) => {
+                info!("Loaded {} tileset sprites.", tileset.sprites.len());
+                Some(tileset)
+            }
+            Err(why) => {
+                warn!("Could not load the tileset, drawing thumbnails with colors: {why}");
+                None
+            }
+        }
+    }
+
+    /// Cuts the image into sprites, named by `ids`.
+    fn decode(image: &[u8], ids: &str) -> std::result::Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(image);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let pixels = buffer[..info.buffer_size()]
+            .chunks_exact(info.color_type.samples())
+            .map(|pixel| match *pixel {
+                [gray] => [gray, gray, gray, u8::MAX],
+                [gray, alpha] => [gray, gray, gray, alpha],
+                [red, green, blue] => [red, green, blue, u8::MAX],
+                [red, green, blue, alpha] => [red, green, blue, alpha],
+                _ => [0; 4],
+            })
+            .collect::<Vec<Pixel>>();
+        let width = info.width as usize;
+        let columns = width / CELL_SIZE;
+        let rows = info.height as usize / CELL_SIZE;
+        let sprites = ids
+            .lines()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .take(columns * rows)
+            .enumerate()
+            .map(|(index, id)| {
+                let left = index % columns * CELL_SIZE;
+                let top = index / columns * CELL_SIZE;
+                let sprite = (top..top + CELL_SIZE)
+                    .flat_map(|y| &pixels[y * width + left..y * width + left + CELL_SIZE])
+                    .copied()
+                    .collect();
+                (id.to_string(), sprite)
+            })
+            .collect();
+        Ok(Self {
+            sprites,
+            hash: short_hash(&[image, ids.as_bytes()]),
+        })
+    }
+
+    /// The sprite of a cell, or of its two-letter id.
+    fn sprite(&self, cell: &Cell) -> Option<&[Pixel]> {
+        self.sprites
+            .get(&cell.to_string())
+            .or_else(|| self.sprites.get(cell.id()))
+            .map(Vec::as_slice)
+    }
+}
+
+impl Thumbnails {
+    /// Loads the tileset (see [`Tileset::load`]).
+    #[must_use]
+    pub fn new(db: SharedAppState) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            tileset: Tileset::load(),
+            cache: DashMap::new(),
+        })
+    }
+
+    /// Returns a level's thumbnail, drawing it if the
+    /// level changed since it was last drawn.
+    fn get(&self, level: Level<Validated>) -> Result<Thumbnail> {
+        let tileset = self.tileset.as_ref().map_or("", |tileset| &tileset.hash);
+        let data = level.data.to_string();
+        let etag = format!("\"{}\"", short_hash(&[data.as_bytes(), tileset.as_bytes()]));
+        if let Some(thumbnail) = self.cache.get(&level.id) {
+            if thumbnail.etag == etag {
+                return Ok(thumbnail.clone());
+            }
+        }
+        let level = level.into_parsed()?;
+        let thumbnail = Thumbnail {
+            etag,
+            png: Bytes::from(draw(&level, self.tileset.as_ref())?),
+        };
+        self.cache.insert(level.id, thumbnail.clone());
+        Ok(thumbnail)
+    }
+}
+
+/// Returns a level's thumbnail as a PNG image, e.g.
+/// `/voyager/thumb/4f0a6c1be2d93875.png`.
+///
+/// The response has an `ETag` header, which changes whenever the level
+/// is edited. If the request's `If-None-Match` header matches it,
+/// Voyager instead returns 304 NOT MODIFIED and no image.
+///
+/// Levels hidden for having been reported too many times only have
+/// thumbnails for Web UI users.
+///
+/// Returns 200 OK and the image, or 304 NOT MODIFIED. Returns
+/// 400 BAD REQUEST on an invalid public ID (or a file name not
+/// ending in `.png`). Returns 404 NOT FOUND if there is no such
+/// level, or it is hidden.
+pub async fn thumbnail(
+    auth_session: AuthSession,
+    State(thumbnails): State<Arc<Thumbnails>>,
+    Path(file): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let id = file.strip_suffix(".png").ok_or(Error::InvalidId)?.parse()?;
+    if thumbnails.db.is_hidden(id) && auth_session.user.is_none() {
+        return Err(Error::LevelNotFound);
+    }
+    let level = thumbnails.db.get(id).inspect_err(|_| {
+        thumbnails.cache.remove(&id);
+    })?;
+    let thumbnail = thumbnails.get(level)?;
+    let cache_headers = [
+        (ETAG, thumbnail.etag.clone()),
+        (CACHE_CONTROL, CACHE_CONTROL_VALUE.to_string()),
+    ];
+    let not_modified = headers
+        .get(IF_NONE_MATCH)
+        .and_then(|tags| tags.to_str().ok())
+        .is_some_and(|tags| {
+            tags.split(',')
+                .any(|tag| tag.trim() == "*" || tag.trim() == thumbnail.etag)
+        });
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+    Ok((
+        StatusCode::OK,
+        [(CONTENT_TYPE, "image/png".to_string())],
+        cache_headers,
+        thumbnail.png,
+    )
+        .into_response())
+}
+
+/// Draws a level as a PNG image, see the module documentation.
+/// Tiles or objects that cannot be decoded are left out.
+fn draw(level: &Parsed, tileset: Option<&Tileset>) -> Result<Vec<u8>> {
+    let tiles = level.tiles.decode();
+    let objects = level.objects.decode();
+    let rows = [&tiles, &objects]
+        .into_iter()
+        .flatten()
+        .map(Grid::rows)
+        .max()
+        .unwrap_or(1);
+    let width = GRID_WIDTH * CELL_SIZE;
+    let mut canvas = vec![[0, 0, 0, u8::MAX]; width * rows * CELL_SIZE];
+    for (grid, object) in [(&tiles, false), (&objects, true)] {
+        for (x, y, cell) in grid.iter().flat_map(Grid::cells) {
+            // empty objects
+            if object && cell.id() == "em" {
+                continue;
+            }
+            let sprite = tileset.and_then(|tileset| tileset.sprite(cell));
+            // objects without a sprite are drawn smaller, so that
+            // the tile underneath them can still be seen
+            let inset = if object && sprite.is_none() {
+                CELL_SIZE / 4
+            } else {
+                0
+            };
+            for dy in inset..CELL_SIZE - inset {
+                for dx in inset..CELL_SIZE - inset {
+                    let source = sprite
+                        .map_or_else(|| color(cell.id()), |sprite| sprite[dy * CELL_SIZE + dx]);
+                    let target = &mut canvas[(y * CELL_SIZE + dy) * width + x * CELL_SIZE + dx];
+                    *target = blend(source, *target);
+                }
+            }
+        }
+    }
+    let mut png = Vec::new();
+    // thumbnails are at most a few hundred pixels big
+    #[allow(clippy::cast_possible_truncation)]
+    let mut encoder = png::Encoder::new(&mut png, width as u32, (rows * CELL_SIZE) as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(canvas.as_flattened())?;
+    writer.finish()?;
+    Ok(png)
+}
+
+/// The color a cell without a sprite is drawn with. Well-known cells
+/// get fitting colors, every other cell gets one derived from its id.
+fn color(id: &str) -> Pixel {
+    match id {
+        "pt" => [0, 0, 0, u8::MAX],
+        "fl" => [96, 96, 104, u8::MAX],
+        "wa" => [200, 200, 208, u8::MAX],
+        "ex" => [240, 200, 40, u8::MAX],
+        "pl" => [60, 140, 255, u8::MAX],
+        _ => {
+            let [red, green, blue, ..]: [u8; 32] = Sha256::digest(id).into();
+            [red, green, blue, u8::MAX]
+        }
+    }
+}
+
+/// Draws a pixel over another, by its alpha.
+fn blend(source: Pixel, target: Pixel) -> Pixel {
+    let alpha = u16::from(source[3]);
+    let mix = |source: u8, target: u8| {
+        let mixed = (u16::from(source) * alpha + u16::from(target) * (255 - alpha)) / 255;
+        // at most 255, as alpha is at most 255
+        #[allow(clippy::cast_possible_truncation)]
+        let mixed = mixed as u8;
+        mixed
+    };
+    [
+        mix(source[0], target[0]),
+        mix(source[1], target[1]),
+        mix(source[2], target[2]),
+        u8::MAX,
+    ]
+}
+
+/// The first 16 hexadecimal digits of the SHA-256 hash of the parts.
+fn short_hash(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&hasher.finalize()[..8]);
+    format!("{:016x}", u64::from_be_bytes(bytes))
+}
@@ -0,0 +1,440 @@
+//! Exporting and importing levels, see [`export`] and [`import`].
+//!
+//! Levels are exported either as newline-delimited JSON, one [`Record`]
+//! per line, or as a directory of `.vsl` files, each holding one level's
+//! data (see [`Data`](crate::utils::level::Data)) and nothing else.
+//!
+//! Only levels (and their status, owner, and stats) are exported.
+//! Orphans, creator accounts, revisions, and leaderboards are not,
+//! and are lost when importing into a new database.
+//!
+//! Keys cannot be exported, as only their hashes are stored. Imported
+//! levels keep their public ID and key hash (and therefore their key)
+//! if the record has them. Otherwise, they are given new ones, and
+//! the new keys are printed.
+
+use crate::prelude::*;
+use crate::utils::{
+    cli::{Conflict, Format},
+    level::{
+        Author, Brand, Burdens, Description, Edited, KeyHash, Music, Name, Objects, Tiles,
+        Uploaded, Version,
+    },
+    server::AppState,
+    stats::Stats,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs::{create_dir_all, read_dir, read_to_string, write},
+    net::{IpAddr, Ipv4Addr},
+    path::Path,
+};
+
+/// Whether a level is public, hidden for having been reported
+/// too many times, or held for review.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// The level is in the level list.
+    #[default]
+    Public,
+    /// The level is hidden from the level list, see
+    /// [`Config::report_threshold`](crate::utils::config::Config::report_threshold).
+    Hidden,
+    /// The level (or an edit of it) is held for review,
+    /// see [`Pending`](crate::utils::pending::Pending).
+    Pending,
+}
+
+/// One level, as a line of an NDJSON export.
+///
+/// The name, description, music, and author are decoded,
+/// unlike in [`Data`](crate::utils::level::Data). Everything
+/// after the objects is optional when importing.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Record {
+    /// The level's public ID, see [`PublicId`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// The hash of the level's key, as 64 hexadecimal digits. See [`KeyHash`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_hash: Option<String>,
+    /// The uploader's IP adress.
+    #[serde(default = "unknown_uploader")]
+    pub uploader: IpAddr,
+    /// See [`Version`].
+    pub version: Version,
+    /// See [`Name`].
+    pub name: Name,
+    /// See [`Description`].
+    pub description: Description,
+    /// See [`Music`].
+    pub music: Music,
+    /// See [`Author`].
+    pub author: Author,
+    /// See [`Brand`].
+    pub brand: Brand,
+    /// See [`Uploaded`].
+    pub uploaded: Uploaded,
+    /// See [`Edited`].
+    pub edited: Edited,
+    /// See [`Burdens`].
+    pub burdens: Burdens,
+    /// See [`Tiles`].
+    pub tiles: Tiles,
+    /// See [`Objects`].
+    pub objects: Objects,
+    /// See [`Status`].
+    #[serde(default)]
+    pub status: Status,
+    /// Why the level is held for review, if it is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// The brand of the creator the level belongs to, if any.
+    /// See [`Creator`](crate::utils::creator::Creator).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<Brand>,
+    /// See [`Stats`].
+    #[serde(default)]
+    pub stats: Stats,
+}
+
+/// The uploader of levels imported without one.
+const fn unknown_uploader() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+}
+
+impl Record {
+    /// Creates a record of a level, and its status and extra metadata.
+    pub fn new(level: Parsed, status: Status, reason: Option<String>, db: &AppState) -> Self {
+        Self {
+            id: Some(level.id.to_string()),
+            key_hash: Some(level.key_hash.to_string()),
+            uploader: level.uploader,
+            status,
+            reason,
+            owner: db.owner(level.id),
+            stats: db.stats(level.id).unwrap_or_default(),
+            version: level.version,
+            name: level.name,
+            description: level.description,
+            music: level.music,
+            author: level.author,
+            brand: level.brand,
+            uploaded: level.uploaded,
+            edited: level.edited,
+            burdens: level.burdens,
+            tiles: level.tiles,
+            objects: level.objects,
+        }
+    }
+
+    /// Validates the record, as if it was uploaded, and turns
+    /// it into a level to import. Returns the level's new key
+    /// if the record had no key hash.
+    fn into_import(self) -> Result<Import> {
+        let given_id = self.id.as_deref().map(str::parse).transpose()?;
+        let key_hash = self
+            .key_hash
+            .as_deref()
+            .map(str::parse::<KeyHash>)
+            .transpose()?;
+        // re-encoding the level and parsing it once more validates it
+        let data = Parsed {
+            version: self.version,
+            name: self.name,
+            description: self.description,
+            music: self.music,
+            author: self.author,
+            brand: self.brand,
+            uploaded: self.uploaded,
+            edited: self.edited,
+            burdens: self.burdens,
+            tiles: self.tiles,
+            objects: self.objects,
+            id: PublicId::new(),
+            key_hash: Key::generate().hash(),
+            uploader: self.uploader,
+        }
+        .into_level()
+        .data
+        .to_string();
+        let (mut level, key) = Level::new(data, self.uploader);
+        let key = if let Some(key_hash) = key_hash {
+            level.key_hash = key_hash;
+            None
+        } else {
+            Some(key)
+        };
+        if let Some(id) = given_id {
+            level.id = id;
+        }
+        let level = level.into_parsed()?;
+        if self.owner.is_some_and(|owner| owner != level.brand) {
+            return Err(Error::NotOwner);
+        }
+        Ok(Import {
+            level,
+            key,
+            given_id: given_id.is_some(),
+            status: self.status,
+            reason: self.reason,
+            owner: self.owner,
+            stats: self.stats,
+        })
+    }
+}
+
+/// A validated level to import.
+struct Import {
+    level: Parsed,
+    /// The level's new key, if it was given one.
+    key: Option<Key>,
+    /// Whether the level kept its public ID.
+    given_id: bool,
+    status: Status,
+    reason: Option<String>,
+    owner: Option<Brand>,
+    stats: Stats,
+}
+
+impl Import {
+    /// Validates a `.vsl` file's level data, as if it was uploaded.
+    /// Levels without dates are dated today.
+    fn from_vsl(data: &str) -> Result<Self> {
+        let (level, key) = Level::new(data.trim().to_string(), unknown_uploader());
+        let mut level = level.into_parsed()?;
+        if level.uploaded.to_string().is_empty() || level.edited.to_string().is_empty() {
+            level.set_dates_to_now();
+        }
+        Ok(Self {
+            level,
+            key: Some(key),
+            given_id: false,
+            status: Status::Public,
+            reason: None,
+            owner: None,
+            stats: Stats::default(),
+        })
+    }
+
+    /// Returns the public ID of the existing level this level
+    /// conflicts with, if any. Levels that kept their public ID
+    /// conflict with the level with the same ID (and the same
+    /// status), others with the level they are a duplicate of.
+    fn conflict(&self, db: &AppState) -> Option<PublicId> {
+        let id = self.level.id;
+        if self.status == Status::Pending {
+            return db.is_pending(id).then_some(id);
+        }
+        if self.given_id {
+            return db.contains(id).then_some(id);
+        }
+        db.find_duplicate(&self.level)
+    }
+}
+
+/// Exports every level (including levels held for review)
+/// from `voyager/levels.db`, see the module documentation.
+/// Orphans, creator accounts, revisions, and leaderboards
+/// are not exported.
+///
+/// # Errors
+/// Returns an error if the export could not be written.
+pub fn export(format: Format, output: &Path) -> Result<()> {
+    let db = AppState::load();
+    let live = db.parsed_levels().into_iter().map(|level| {
+        let status = if db.is_hidden(level.id) {
+            Status::Hidden
+        } else {
+            Status::Public
+        };
+        (level, status, None)
+    });
+    let pending = db
+        .pending()
+        .into_iter()
+        .map(|(level, reason)| (level, Status::Pending, Some(reason)));
+    let mut count = 0;
+    match format {
+        Format::Ndjson => {
+            let mut lines = String::new();
+            for (level, status, reason) in live.chain(pending) {
+                lines.push_str(&serde_json::to_string(&Record::new(
+                    level, status, reason, &db,
+                ))?);
+                lines.push('\n');
+                count += 1;
+            }
+            write(output, lines)?;
+        }
+        Format::Vsl => {
+            create_dir_all(output)?;
+            // held edits would overwrite their public level
+            for (level, _, _) in live {
+                let id = level.id;
+                write(
+                    output.join(format!("{id}.vsl")),
+                    level.into_level().data.to_string(),
+                )?;
+                count += 1;
+            }
+        }
+    }
+    println!("Exported {count} levels to {}.", output.display());
+    Ok(())
+}
+
+/// Imports levels into `voyager/levels.db`, see the module documentation.
+///
+/// Every level is validated as if it was uploaded, including
+/// the word list, which may mask words or hold the level for
+/// review. Invalid levels are reported and left out.
+///
+/// # Errors
+/// Returns an error if the levels could not be read, or
+/// [`Error::ImportConflict`] if a level already exists and
+/// conflicts abort the import, in which case nothing is imported.
+pub fn import(format: Format, input: &Path, on_conflict: Conflict, dry_run: bool) -> Result<()> {
+    let db = AppState::load();
+    let sources = match format {
+        Format::Ndjson => read_to_string(input)?
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(number, line)| (format!("line {}", number + 1), line.to_string()))
+            .collect::<Vec<(String, String)>>(),
+        Format::Vsl => {
+            let mut files = Vec::new();
+            for entry in read_dir(input)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|extension| extension == "vsl") {
+                    files.push((path.display().to_string(), read_to_string(&path)?));
+                }
+            }
+            files.sort();
+            files
+        }
+    };
+
+    let mut seen = HashSet::new();
+    let mut plan = Vec::new();
+    let mut invalid = 0;
+    for (source, contents) in sources {
+        let import = match format {
+            Format::Ndjson => serde_json::from_str::<Record>(&contents)
+                .map_err(Error::from)
+                .and_then(Record::into_import),
+            Format::Vsl => Import::from_vsl(&contents),
+        }
+        .and_then(|mut import| {
+            if db.filter(&mut import.level)? && import.status != Status::Pending {
+                import.status = Status::Pending;
+                import.reason = Some("word list".to_string());
+            }
+            let pending = import.status == Status::Pending;
+            if seen.insert((import.level.id, pending)) {
+                Ok(import)
+            } else {
+                Err(Error::Duplicate)
+            }
+        });
+        match import {
+            Ok(import) => {
+                let conflict = import.conflict(&db);
+                if let (Some(id), Conflict::Abort) = (conflict, on_conflict) {
+                    return Err(Error::ImportConflict(id));
+                }
+                plan.push((import, conflict));
+            }
+            Err(why) => {
+                eprintln!("{source}: {why}");
+                invalid += 1;
+            }
+        }
+    }
+
+    let mut imported = 0;
+    let mut replaced = 0;
+    let mut skipped = 0;
+    let run = || {
+        for (import, conflict) in plan {
+            let action = match (conflict, on_conflict) {
+                (None, _) => "import",
+                (Some(_), Conflict::Replace) => "replace",
+                (Some(_), _) => "skip",
+            };
+            if dry_run {
+                let id = conflict.unwrap_or(import.level.id);
+                println!("would {action} {id}: {}", import.level.name);
+            }
+            match action {
+                "import" => imported += 1,
+                "replace" => replaced += 1,
+                _ => skipped += 1,
+            }
+            if dry_run || action == "skip" {
+                continue;
+            }
+            apply(&db, import, conflict)?;
+        }
+        Ok::<(), Error>(())
+    };
+    if dry_run {
+        // not batched, since a batch always saves, which would rewrite
+        // (or create) the database even though nothing changed
+        run()?;
+    } else {
+        // saved once at the end, instead of after every level
+        db.batch(run)?;
+    }
+    if dry_run {
+        println!("Would import {imported} and replace {replaced} levels, skipping {skipped} existing and {invalid} invalid levels.");
+    } else {
+        println!("Imported {imported} and replaced {replaced} levels, skipped {skipped} existing and {invalid} invalid levels.");
+    }
+    Ok(())
+}
+
+/// Imports one level, replacing the existing level it conflicts with,
+/// if any. Replacing a level keeps its public ID, key, and upload date.
+fn apply(db: &AppState, import: Import, conflict: Option<PublicId>) -> Result<()> {
+    let Import {
+        mut level,
+        key,
+        status,
+        reason,
+        owner,
+        stats,
+        ..
+    } = import;
+    if let Some(id) = conflict {
+        let existing = if status == Status::Pending {
+            db.get_pending(id)?
+        } else {
+            db.get(id)?
+        };
+        level.id = id;
+        level.key_hash = existing.key_hash;
+        level.set_uploaded_from(existing)?;
+    } else if let Some(key) = key {
+        println!("{} {key}", level.id);
+    }
+    let id = level.id;
+    let level = level.into_level();
+    match status {
+        Status::Pending if conflict.is_some() => db.update_pending(level)?,
+        Status::Pending => db.hold(level, reason.as_deref().unwrap_or("import")),
+        Status::Public | Status::Hidden => {
+            db.insert(level);
+            db.set_hidden(id, status == Status::Hidden);
+            db.set_stats(id, stats);
+        }
+    }
+    if let Some(owner) = owner {
+        db.set_owner(id, owner);
+    }
+    Ok(())
+}
@@ -4,9 +4,9 @@ use axum::{
     response::Redirect,
 };
 
-pub async fn delete(State(db): State<SharedAppState>, key: Path<String>) -> Redirect {
-    if let Ok(key) = &key.parse() {
-        let _ = db.delete(key);
+pub async fn delete(State(db): State<SharedAppState>, id: Path<String>) -> Redirect {
+    if let Ok(id) = id.parse() {
+        let _ = db.delete(id);
     }
     Redirect::to("/voyager/webui")
 }
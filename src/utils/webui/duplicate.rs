@@ -0,0 +1,16 @@
+use askama_axum::{IntoResponse, Template};
+use axum::extract::State;
+
+use crate::prelude::*;
+
+#[derive(Template)]
+#[template(path = "duplicates.html")]
+struct Duplicates {
+    pairs: Vec<(Parsed, Parsed, f64)>,
+}
+
+pub async fn list(State(db): State<SharedAppState>) -> impl IntoResponse {
+    Duplicates {
+        pairs: db.possible_duplicates(),
+    }
+}
@@ -1,27 +1,45 @@
 use askama_axum::{IntoResponse, Template};
-use axum::{extract::State, response::Html};
+use axum::{
+    extract::{Query, State},
+    response::Html,
+};
 
 use crate::prelude::*;
+use crate::utils::{index::Filter, stats::Stats};
 
 #[derive(Template)]
 #[template(path = "index.html")]
 struct Index {
     username: String,
-    levels: Vec<Parsed>,
+    levels: Vec<(Parsed, Stats)>,
+    filter: Filter,
 }
 
 pub async fn index(
     auth_session: AuthSession,
+    Query(filter): Query<Filter>,
     State(db): State<SharedAppState>,
 ) -> impl IntoResponse {
-    let levels = db.parsed_levels();
-    auth_session
-        .user
-        .map_or(Html(r"unauthorized").into_response(), |user| {
+    let levels = match db.parsed_levels_matching(&filter) {
+        Ok(levels) => levels,
+        Err(error) => return error.into_response(),
+    };
+    let levels = levels
+        .into_iter()
+        .map(|level| {
+            let stats = db.stats(level.id).unwrap_or_default();
+            (level, stats)
+        })
+        .collect();
+    auth_session.user.map_or_else(
+        || Html(r"unauthorized").into_response(),
+        |user| {
             Index {
                 username: user.username,
                 levels,
+                filter,
             }
             .into_response()
-        })
+        },
+    )
 }
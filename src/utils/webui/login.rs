@@ -37,8 +37,14 @@ pub async fn post(
     Form(creds): Form<Credentials>,
 ) -> impl IntoResponse {
     let user = match auth_session.authenticate(creds.clone()).await {
-        Ok(Some(user)) => user,
-        Ok(None) => return StatusCode::UNAUTHORIZED.into_response(),
+        Ok(Some(user)) => {
+            metrics::counter!("voyager_logins_total", "result" => "success").increment(1);
+            user
+        }
+        Ok(None) => {
+            metrics::counter!("voyager_logins_total", "result" => "failure").increment(1);
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
         Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     };
 
@@ -1,4 +1,10 @@
 pub mod ban;
 pub mod delete;
+pub mod duplicate;
 pub mod index;
 pub mod login;
+pub mod orphan;
+pub mod pending;
+pub mod quota;
+pub mod report;
+pub mod revision;
@@ -0,0 +1,34 @@
+use askama_axum::{IntoResponse, Template};
+use axum::{
+    extract::{Path, State},
+    response::Redirect,
+};
+use time::OffsetDateTime;
+
+use crate::prelude::*;
+
+#[derive(Template)]
+#[template(path = "orphans.html")]
+struct Orphans {
+    orphans: Vec<(Parsed, OffsetDateTime)>,
+}
+
+pub async fn list(State(db): State<SharedAppState>) -> impl IntoResponse {
+    Orphans {
+        orphans: db.orphans(),
+    }
+}
+
+pub async fn adopt(State(db): State<SharedAppState>, id: Path<String>) -> Redirect {
+    if let Ok(id) = id.parse() {
+        let _ = db.adopt_orphan(id);
+    }
+    Redirect::to("/voyager/webui/orphans")
+}
+
+pub async fn purge(State(db): State<SharedAppState>, id: Path<String>) -> Redirect {
+    if let Ok(id) = id.parse() {
+        let _ = db.purge_orphan(id);
+    }
+    Redirect::to("/voyager/webui/orphans")
+}
@@ -0,0 +1,71 @@
+use askama_axum::{IntoResponse, Template};
+use axum::{
+    extract::{Path, State},
+    response::Redirect,
+    Form,
+};
+use serde::Deserialize;
+
+use crate::prelude::*;
+use crate::utils::pending::Decision;
+
+/// How many of the latest decisions are shown.
+const SHOWN_DECISIONS: usize = 50;
+
+#[derive(Template)]
+#[template(path = "pending.html")]
+struct PendingLevels {
+    levels: Vec<(Parsed, String)>,
+    words: usize,
+    premoderation: bool,
+    decisions: Vec<Decision>,
+}
+
+#[derive(Deserialize)]
+pub struct Premoderation {
+    enabled: bool,
+}
+
+pub async fn list(State(db): State<SharedAppState>) -> impl IntoResponse {
+    PendingLevels {
+        levels: db.pending(),
+        words: db.word_count(),
+        premoderation: db.premoderation(),
+        decisions: db.decisions(SHOWN_DECISIONS),
+    }
+}
+
+pub async fn approve(
+    auth_session: AuthSession,
+    State(db): State<SharedAppState>,
+    id: Path<String>,
+) -> Redirect {
+    if let (Ok(id), Some(user)) = (id.parse(), auth_session.user) {
+        let _ = db.approve(id, &user.username);
+    }
+    Redirect::to("/voyager/webui/pending")
+}
+
+pub async fn reject(
+    auth_session: AuthSession,
+    State(db): State<SharedAppState>,
+    id: Path<String>,
+) -> Redirect {
+    if let (Ok(id), Some(user)) = (id.parse(), auth_session.user) {
+        let _ = db.reject(id, &user.username);
+    }
+    Redirect::to("/voyager/webui/pending")
+}
+
+pub async fn premoderation(
+    State(db): State<SharedAppState>,
+    Form(input): Form<Premoderation>,
+) -> Redirect {
+    db.set_premoderation(input.enabled);
+    Redirect::to("/voyager/webui/pending")
+}
+
+pub async fn reload(State(db): State<SharedAppState>) -> Redirect {
+    db.reload_word_list();
+    Redirect::to("/voyager/webui/pending")
+}
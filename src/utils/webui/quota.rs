@@ -0,0 +1,44 @@
+use askama_axum::{IntoResponse, Template};
+use axum::{
+    extract::{Path, State},
+    response::Redirect,
+    Form,
+};
+use serde::Deserialize;
+use std::net::IpAddr;
+
+use crate::prelude::*;
+use crate::utils::quota::Quota;
+
+#[derive(Template)]
+#[template(path = "quotas.html")]
+struct Quotas {
+    overrides: Vec<(IpAddr, Quota)>,
+}
+
+#[derive(Deserialize)]
+pub struct Override {
+    ip: String,
+    daily: u32,
+    live: usize,
+}
+
+pub async fn list(State(db): State<SharedAppState>) -> impl IntoResponse {
+    Quotas {
+        overrides: db.quota_overrides(),
+    }
+}
+
+pub async fn set(State(db): State<SharedAppState>, Form(input): Form<Override>) -> Redirect {
+    let quota = Quota {
+        daily: input.daily,
+        live: input.live,
+    };
+    let _ = db.override_quota(&input.ip, quota);
+    Redirect::to("/voyager/webui/quotas")
+}
+
+pub async fn reset(State(db): State<SharedAppState>, ip: Path<String>) -> Redirect {
+    let _ = db.reset_quota(&ip);
+    Redirect::to("/voyager/webui/quotas")
+}
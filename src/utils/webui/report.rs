@@ -0,0 +1,39 @@
+use askama_axum::{IntoResponse, Template};
+use axum::{
+    extract::{Path, State},
+    response::Redirect,
+};
+
+use crate::prelude::*;
+use crate::utils::report::Report;
+
+#[derive(Template)]
+#[template(path = "reports.html")]
+struct Reports {
+    levels: Vec<(Parsed, Vec<Report>, bool)>,
+}
+
+pub async fn list(State(db): State<SharedAppState>) -> impl IntoResponse {
+    Reports {
+        levels: db.reports(),
+    }
+}
+
+pub async fn dismiss(State(db): State<SharedAppState>, id: Path<String>) -> Redirect {
+    if let Ok(id) = id.parse() {
+        db.dismiss_reports(id);
+    }
+    Redirect::to("/voyager/webui/reports")
+}
+
+pub async fn delete(State(db): State<SharedAppState>, id: Path<String>) -> Redirect {
+    if let Ok(id) = id.parse() {
+        let _ = db.delete(id);
+    }
+    Redirect::to("/voyager/webui/reports")
+}
+
+pub async fn ban(State(db): State<SharedAppState>, ip: Path<String>) -> Redirect {
+    let _ = db.ban(&ip);
+    Redirect::to("/voyager/webui/reports")
+}
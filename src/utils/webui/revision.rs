@@ -0,0 +1,67 @@
+use askama_axum::{IntoResponse, Template};
+use axum::{
+    extract::{Path, State},
+    response::{Html, Redirect},
+};
+
+use crate::prelude::*;
+use crate::utils::revision::Diff;
+
+#[derive(Template)]
+#[template(path = "revisions.html")]
+struct Revisions {
+    id: PublicId,
+    revisions: Vec<(u32, Parsed)>,
+}
+
+#[derive(Template)]
+#[template(path = "diff.html")]
+struct Changes {
+    id: PublicId,
+    from: u32,
+    to: u32,
+    diff: Diff,
+}
+
+pub async fn list(State(db): State<SharedAppState>, id: Path<String>) -> impl IntoResponse {
+    let Ok(id) = id.parse::<PublicId>() else {
+        return Html(r"invalid id").into_response();
+    };
+    let revisions = db
+        .revisions(id)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|revision| Some((revision.number, revision.level.into_parsed().ok()?)))
+        .collect();
+    Revisions { id, revisions }.into_response()
+}
+
+pub async fn diff(
+    State(db): State<SharedAppState>,
+    Path((id, from, to)): Path<(String, u32, u32)>,
+) -> impl IntoResponse {
+    let Ok(id) = id.parse::<PublicId>() else {
+        return Html(r"invalid id").into_response();
+    };
+    let parsed = |number| db.revision(id, number).and_then(Level::into_parsed);
+    match (parsed(from), parsed(to)) {
+        (Ok(old), Ok(new)) => Changes {
+            id,
+            from,
+            to,
+            diff: Diff::new(&old, &new),
+        }
+        .into_response(),
+        _ => Html(r"revision not found").into_response(),
+    }
+}
+
+pub async fn revert(
+    State(db): State<SharedAppState>,
+    Path((id, number)): Path<(String, u32)>,
+) -> Redirect {
+    if let Ok(id) = id.parse() {
+        let _ = db.revert(id, number);
+    }
+    Redirect::to(&format!("/voyager/webui/revisions/{id}"))
+}